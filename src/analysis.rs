@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use crate::linalg::Vec3;
+use crate::mesh::Mesh;
+
+const VERTICES_PER_TRIANGLE: usize = 3;
+
+/// Edge and triangle defects of a mesh. A closed, consistently oriented
+/// surface has none of them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Topology {
+    /// Edges used by a single triangle only, i.e. holes in the surface.
+    pub boundary_edges: usize,
+    /// Edges shared by more than two triangles.
+    pub non_manifold_edges: usize,
+    /// Edges whose two triangles traverse them in the same direction.
+    pub inconsistent_edges: usize,
+    /// Triangles without area.
+    pub degenerate_triangles: usize,
+}
+
+impl Topology {
+    pub fn is_manifold(&self) -> bool {
+        self.boundary_edges == 0 && self.non_manifold_edges == 0 && self.inconsistent_edges == 0
+    }
+}
+
+fn triangles(mesh: &Mesh) -> impl Iterator<Item = [Vec3<f32>; 3]> + '_ {
+    mesh.indices
+        .chunks_exact(VERTICES_PER_TRIANGLE)
+        .map(|triangle| [0, 1, 2].map(|idx| mesh.position(triangle[idx])))
+}
+
+pub fn surface_area(mesh: &Mesh) -> f64 {
+    triangles(mesh)
+        .map(|[a, b, c]| (b - a).cross(&(c - a)).length() as f64 * 0.5)
+        .fold(0.0, |sum, area| sum + area)
+}
+
+/// Signed volume enclosed by the mesh, negative if the triangles are wound
+/// clockwise. Only meaningful for closed surfaces.
+pub fn volume(mesh: &Mesh) -> f64 {
+    triangles(mesh)
+        .map(|[a, b, c]| a.dot(&b.cross(&c)) as f64 / 6.0)
+        .fold(0.0, |sum, volume| sum + volume)
+}
+
+pub fn topology(mesh: &Mesh) -> Topology {
+    let mut topology = Topology::default();
+    // Number of uses and sum of directions per undirected edge.
+    let mut edges: HashMap<(u32, u32), (usize, i32)> = HashMap::new();
+
+    for (triangle, corners) in mesh
+        .indices
+        .chunks_exact(VERTICES_PER_TRIANGLE)
+        .zip(triangles(mesh))
+    {
+        let [a, b, c] = corners;
+        if (b - a).cross(&(c - a)).length() == 0.0 {
+            topology.degenerate_triangles += 1;
+        }
+        for idx in 0..VERTICES_PER_TRIANGLE {
+            let (from, to) = (triangle[idx], triangle[(idx + 1) % VERTICES_PER_TRIANGLE]);
+            if from == to {
+                continue;
+            }
+            let (key, direction) = if from < to {
+                ((from, to), 1)
+            } else {
+                ((to, from), -1)
+            };
+            let edge = edges.entry(key).or_default();
+            edge.0 += 1;
+            edge.1 += direction;
+        }
+    }
+
+    for (uses, directions) in edges.into_values() {
+        match uses {
+            1 => topology.boundary_edges += 1,
+            2 if directions != 0 => topology.inconsistent_edges += 1,
+            2 => {}
+            _ => topology.non_manifold_edges += 1,
+        }
+    }
+
+    topology
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::stl;
+
+    #[test]
+    fn test_cube_analysis() {
+        let cube = std::fs::read("tests/files/cube.stl").unwrap();
+        let mesh = stl::parse(&cube).unwrap().to_mesh(0.0);
+
+        assert_eq!(surface_area(&mesh), 6.0);
+        assert_eq!(volume(&mesh), 1.0);
+        assert!(topology(&mesh).is_manifold());
+    }
+
+    #[test]
+    fn test_empty_mesh() {
+        let mesh = Mesh::default();
+
+        // Not the `-0.0` of an empty floating point sum.
+        assert!(surface_area(&mesh).is_sign_positive());
+        assert!(volume(&mesh).is_sign_positive());
+    }
+
+    #[test]
+    fn test_open_mesh_is_not_manifold() {
+        let cube = std::fs::read("tests/files/cube.stl").unwrap();
+        let mut mesh = stl::parse(&cube).unwrap().to_mesh(0.0);
+        mesh.indices
+            .truncate(mesh.indices.len() - VERTICES_PER_TRIANGLE);
+
+        let topology = topology(&mesh);
+
+        assert_eq!(topology.boundary_edges, 3);
+        assert!(!topology.is_manifold());
+    }
+}
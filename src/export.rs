@@ -0,0 +1,42 @@
+use std::fmt::Write;
+
+use crate::mesh::Mesh;
+
+const AXES: usize = 3;
+
+/// Encodes the mesh as a Wavefront OBJ file with shared vertices.
+pub fn to_obj(mesh: &Mesh) -> String {
+    let mut text = String::new();
+    for v in mesh.positions.chunks_exact(AXES) {
+        writeln!(text, "v {} {} {}", v[0], v[1], v[2]).unwrap();
+    }
+    // OBJ indices are one-based.
+    for triangle in mesh.indices.chunks_exact(AXES) {
+        writeln!(
+            text,
+            "f {} {} {}",
+            triangle[0] + 1,
+            triangle[1] + 1,
+            triangle[2] + 1
+        )
+        .unwrap();
+    }
+    text
+}
+
+/// Encodes the mesh as an ASCII PLY file with shared vertices.
+pub fn to_ply(mesh: &Mesh) -> String {
+    let mut text = String::new();
+    writeln!(text, "ply\nformat ascii 1.0").unwrap();
+    writeln!(text, "element vertex {}", mesh.positions.len() / AXES).unwrap();
+    writeln!(text, "property float x\nproperty float y\nproperty float z").unwrap();
+    writeln!(text, "element face {}", mesh.indices.len() / AXES).unwrap();
+    writeln!(text, "property list uchar uint vertex_indices\nend_header").unwrap();
+    for v in mesh.positions.chunks_exact(AXES) {
+        writeln!(text, "{} {} {}", v[0], v[1], v[2]).unwrap();
+    }
+    for triangle in mesh.indices.chunks_exact(AXES) {
+        writeln!(text, "3 {} {} {}", triangle[0], triangle[1], triangle[2]).unwrap();
+    }
+    text
+}
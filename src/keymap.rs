@@ -0,0 +1,182 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::view::{Orbit, RenderMode, StandardView};
+
+/// Something a key press can do to the view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    View(StandardView),
+    /// Back to the initial view of the model.
+    ResetView,
+    /// Frame the whole model without changing the direction of view.
+    FitToModel,
+    RenderMode(RenderMode),
+    NextRenderMode,
+    ToggleShading,
+    ToggleGrid,
+    ToggleAxes,
+    ToggleBoundingBox,
+    /// Switch between perspective and orthographic projection.
+    ToggleProjection,
+    Orbit(Orbit),
+}
+
+const ACTION_NAMES: [(&str, Action); 23] = [
+    ("front", Action::View(StandardView::Front)),
+    ("back", Action::View(StandardView::Back)),
+    ("left", Action::View(StandardView::Left)),
+    ("right", Action::View(StandardView::Right)),
+    ("top", Action::View(StandardView::Top)),
+    ("bottom", Action::View(StandardView::Bottom)),
+    ("iso", Action::View(StandardView::Iso)),
+    ("reset", Action::ResetView),
+    ("fit", Action::FitToModel),
+    ("solid", Action::RenderMode(RenderMode::Solid)),
+    ("wireframe", Action::RenderMode(RenderMode::Wireframe)),
+    (
+        "solid-edges",
+        Action::RenderMode(RenderMode::SolidWithEdges),
+    ),
+    (
+        "feature-edges",
+        Action::RenderMode(RenderMode::FeatureEdges),
+    ),
+    ("render-mode", Action::NextRenderMode),
+    ("shading", Action::ToggleShading),
+    ("grid", Action::ToggleGrid),
+    ("axes", Action::ToggleAxes),
+    ("bounding-box", Action::ToggleBoundingBox),
+    ("projection", Action::ToggleProjection),
+    ("orbit-left", Action::Orbit(Orbit::Left)),
+    ("orbit-right", Action::Orbit(Orbit::Right)),
+    ("orbit-up", Action::Orbit(Orbit::Up)),
+    ("orbit-down", Action::Orbit(Orbit::Down)),
+];
+
+const DEFAULT_BINDINGS: [(&str, &str); 19] = [
+    ("1", "front"),
+    ("2", "back"),
+    ("3", "left"),
+    ("4", "right"),
+    ("5", "top"),
+    ("6", "bottom"),
+    ("0", "iso"),
+    ("r", "reset"),
+    ("f", "fit"),
+    ("w", "render-mode"),
+    ("s", "shading"),
+    ("g", "grid"),
+    ("a", "axes"),
+    ("b", "bounding-box"),
+    ("p", "projection"),
+    ("ArrowLeft", "orbit-left"),
+    ("ArrowRight", "orbit-right"),
+    ("ArrowUp", "orbit-up"),
+    ("ArrowDown", "orbit-down"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeymapError {
+    /// A binding is not of the form `key=action`.
+    MissingSeparator(String),
+    UnknownAction(String),
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeymapError::MissingSeparator(binding) => {
+                write!(f, "keymap: expected key=action, found {:?}", binding)
+            }
+            KeymapError::UnknownAction(action) => {
+                write!(f, "keymap: unknown action {:?}", action)
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+impl FromStr for Action {
+    type Err = KeymapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ACTION_NAMES
+            .iter()
+            .find(|(name, _)| *name == s)
+            .map(|(_, action)| *action)
+            .ok_or_else(|| KeymapError::UnknownAction(s.to_string()))
+    }
+}
+
+/// Keys, named like the `key` of a DOM keyboard event, and the actions they
+/// trigger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap(Vec<(String, Action)>);
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let mut keymap = Self(vec![]);
+        for (key, action) in DEFAULT_BINDINGS {
+            keymap.bind(key, action.parse().unwrap());
+        }
+        keymap
+    }
+}
+
+impl Keymap {
+    /// Binds `key` to `action`, replacing what it was bound to before.
+    pub fn bind(&mut self, key: &str, action: Action) {
+        self.0.retain(|(bound, _)| bound != key);
+        self.0.push((key.to_string(), action));
+    }
+
+    pub fn action(&self, key: &str) -> Option<Action> {
+        self.0
+            .iter()
+            .find(|(bound, _)| bound == key)
+            .map(|(_, action)| *action)
+    }
+
+    /// Overrides bindings from a comma separated list like
+    /// `w=shading,s=render-mode`.
+    pub fn configure(&mut self, bindings: &str) -> Result<(), KeymapError> {
+        for binding in bindings.split(',').filter(|binding| !binding.is_empty()) {
+            // Split at the last `=` so `=` itself can be bound.
+            let (key, action) = binding
+                .rsplit_once('=')
+                .filter(|(key, _)| !key.is_empty())
+                .ok_or_else(|| KeymapError::MissingSeparator(binding.to_string()))?;
+            self.bind(key, action.parse()?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_configure_keymap() {
+        let mut keymap = Keymap::default();
+        assert_eq!(keymap.action("w"), Some(Action::NextRenderMode));
+        assert_eq!(keymap.action("x"), None);
+
+        keymap.configure("w=shading,==fit,x=front").unwrap();
+        assert_eq!(keymap.action("w"), Some(Action::ToggleShading));
+        assert_eq!(keymap.action("="), Some(Action::FitToModel));
+        assert_eq!(keymap.action("x"), Some(Action::View(StandardView::Front)));
+        assert_eq!(keymap.action("s"), Some(Action::ToggleShading));
+
+        assert_eq!(
+            keymap.configure("q=quit"),
+            Err(KeymapError::UnknownAction("quit".to_string()))
+        );
+        assert_eq!(
+            keymap.configure("front"),
+            Err(KeymapError::MissingSeparator("front".to_string()))
+        );
+    }
+}
@@ -0,0 +1,9 @@
+pub mod analysis;
+pub mod export;
+pub mod keymap;
+pub mod linalg;
+pub mod mesh;
+pub mod overlay;
+pub mod render;
+pub mod stl;
+pub mod view;
@@ -1,8 +1,8 @@
-use std::ops::{Add, AddAssign, MulAssign, Sub, SubAssign};
+use std::ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign};
 
 pub type Vec3<T> = Vector<T, 3>;
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Vector<T, const SIZE: usize>([T; SIZE]);
 
 impl<T, const SIZE: usize> Vector<T, SIZE>
@@ -18,10 +18,286 @@ where
     pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
         self.0.get_mut(idx)
     }
+    /// Returns the largest element, NaNs are skipped unless all elements are NaN.
     pub fn get_max(&self) -> T {
-        let mut tmp = self.0;
-        tmp.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
-        *tmp.first().unwrap()
+        self.0.into_iter().fold(self.0[0], |max, value| {
+            // Only NaN is incomparable to itself.
+            if value > max || max.partial_cmp(&max).is_none() {
+                value
+            } else {
+                max
+            }
+        })
+    }
+}
+
+impl Vec3<f32> {
+    pub fn dot(&self, rhs: &Self) -> f32 {
+        self.0.iter().zip(rhs.0).map(|(lhs, rhs)| lhs * rhs).sum()
+    }
+    pub fn cross(&self, rhs: &Self) -> Self {
+        let [a, b] = [self.0, rhs.0];
+        Self([
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ])
+    }
+    pub fn length(&self) -> f32 {
+        self.dot(self).sqrt()
+    }
+    /// Returns the zero vector unchanged instead of dividing by zero.
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length > 0.0 {
+            self.scale(1.0 / length)
+        } else {
+            self
+        }
+    }
+}
+
+/// Unit quaternion representing a rotation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub w: f32,
+    pub v: Vec3<f32>,
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Quaternion {
+    pub const IDENTITY: Self = Self {
+        w: 1.0,
+        v: Vector([0.0; 3]),
+    };
+
+    pub fn from_axis_angle(axis: Vec3<f32>, angle: f32) -> Self {
+        let (sin, cos) = (angle * 0.5).sin_cos();
+        Self {
+            w: cos,
+            v: axis.normalize().scale(sin),
+        }
+    }
+    /// Rotation by `length` radians around the direction of the vector.
+    pub fn from_scaled_axis(axis: Vec3<f32>) -> Self {
+        Self::from_axis_angle(axis, axis.length())
+    }
+    /// Shortest rotation taking the unit vector `from` onto the unit vector
+    /// `to`.
+    pub fn from_rotation_arc(from: Vec3<f32>, to: Vec3<f32>) -> Self {
+        Self {
+            w: 1.0 + from.dot(&to),
+            v: from.cross(&to),
+        }
+        .normalize()
+    }
+    /// Inverse of [`Self::from_scaled_axis`].
+    pub fn to_scaled_axis(self) -> Vec3<f32> {
+        // Take the short way round, q and -q are the same rotation.
+        let q = if self.w < 0.0 { self.scale(-1.0) } else { self };
+        let sin = q.v.length();
+        if sin > 0.0 {
+            q.v.scale(2.0 * sin.atan2(q.w) / sin)
+        } else {
+            q.v.scale(2.0)
+        }
+    }
+    /// Interpolates along the shortest arc, `t` of 0 gives `self` and 1
+    /// gives `other`.
+    pub fn slerp(self, other: Self, t: f32) -> Self {
+        let mut cos = self.w * other.w + self.v.dot(&other.v);
+        let other = if cos < 0.0 {
+            cos = -cos;
+            other.scale(-1.0)
+        } else {
+            other
+        };
+        // Nearly identical rotations would divide by almost zero.
+        let (a, b) = if cos > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let angle = cos.acos();
+            let sin = angle.sin();
+            (((1.0 - t) * angle).sin() / sin, (t * angle).sin() / sin)
+        };
+        Self {
+            w: self.w * a + other.w * b,
+            v: self.v.scale(a) + other.v.scale(b),
+        }
+        .normalize()
+    }
+    pub fn conjugate(self) -> Self {
+        Self {
+            w: self.w,
+            v: self.v.scale(-1.0),
+        }
+    }
+    /// Rescales to unit length to undo drift from repeated multiplication.
+    pub fn normalize(self) -> Self {
+        let length = (self.w * self.w + self.v.dot(&self.v)).sqrt();
+        if length > 0.0 {
+            self.scale(1.0 / length)
+        } else {
+            Self::IDENTITY
+        }
+    }
+    pub fn rotate(&self, point: Vec3<f32>) -> Vec3<f32> {
+        let t = self.v.cross(&point).scale(2.0);
+        point + t.scale(self.w) + self.v.cross(&t)
+    }
+    /// Column-major rotation matrix as used by OpenGL.
+    pub fn to_matrix(&self) -> [f32; 16] {
+        let [x, y, z] = self.v.0;
+        let w = self.w;
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y + w * z),
+            2.0 * (x * z - w * y),
+            0.0,
+            2.0 * (x * y - w * z),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z + w * x),
+            0.0,
+            2.0 * (x * z + w * y),
+            2.0 * (y * z - w * x),
+            1.0 - 2.0 * (x * x + y * y),
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+        ]
+    }
+    fn scale(self, scalar: f32) -> Self {
+        Self {
+            w: self.w * scalar,
+            v: self.v.scale(scalar),
+        }
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Self;
+
+    /// Rotation by `rhs` followed by `self`.
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self {
+            w: self.w * rhs.w - self.v.dot(&rhs.v),
+            v: rhs.v.scale(self.w) + self.v.scale(rhs.w) + self.v.cross(&rhs.v),
+        }
+    }
+}
+
+/// Axis-aligned box spanned by the component-wise minimum and maximum of a set
+/// of points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Vec3<f32>,
+    pub max: Vec3<f32>,
+}
+
+impl BoundingBox {
+    /// Returns `None` if there are no points or any coordinate is NaN or
+    /// infinite, as a single such value would poison the whole box.
+    pub fn from_points<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = [f32; 3]>,
+    {
+        let mut points = points.into_iter();
+        let first = Vec3::from(points.next()?);
+        let mut bounds = Self {
+            min: first,
+            max: first,
+        };
+        if !first.0.iter().all(|v| v.is_finite()) {
+            return None;
+        }
+        for point in points {
+            for (axis, value) in point.into_iter().enumerate() {
+                if !value.is_finite() {
+                    return None;
+                }
+                bounds.min.0[axis] = bounds.min.0[axis].min(value);
+                bounds.max.0[axis] = bounds.max.0[axis].max(value);
+            }
+        }
+        Some(bounds)
+    }
+    pub fn center(&self) -> Vec3<f32> {
+        (self.min + self.max).scale(0.5)
+    }
+    /// Edge lengths along each axis.
+    pub fn extent(&self) -> Vec3<f32> {
+        self.max - self.min
+    }
+    pub fn diagonal(&self) -> f32 {
+        self.extent().length()
+    }
+    /// Smallest sphere around the centre that contains the box.
+    pub fn bounding_sphere(&self) -> Sphere {
+        Sphere {
+            center: self.center(),
+            radius: self.diagonal() * 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere {
+    pub center: Vec3<f32>,
+    pub radius: f32,
+}
+
+impl Default for Sphere {
+    /// The unit sphere.
+    fn default() -> Self {
+        Self {
+            center: Vec3::default(),
+            radius: 1.0,
+        }
+    }
+}
+
+impl Sphere {
+    /// Smallest sphere containing both spheres.
+    pub fn union(&self, other: &Sphere) -> Sphere {
+        let distance = (other.center - self.center).length();
+        if distance + other.radius <= self.radius {
+            return *self;
+        }
+        if distance + self.radius <= other.radius {
+            return *other;
+        }
+        let radius = (distance + self.radius + other.radius) * 0.5;
+        let center =
+            self.center + (other.center - self.center).scale((radius - self.radius) / distance);
+        Sphere { center, radius }
+    }
+}
+
+impl<T, const SIZE: usize> Default for Vector<T, SIZE>
+where
+    T: Copy + Default,
+{
+    fn default() -> Self {
+        Self([T::default(); SIZE])
+    }
+}
+
+impl<T, const SIZE: usize> From<[T; SIZE]> for Vector<T, SIZE> {
+    fn from(value: [T; SIZE]) -> Self {
+        Self(value)
+    }
+}
+
+impl<T, const SIZE: usize> From<Vector<T, SIZE>> for [T; SIZE] {
+    fn from(value: Vector<T, SIZE>) -> Self {
+        value.0
     }
 }
 
@@ -77,3 +353,113 @@ pub trait VecOps<U> {
     fn translate(self, op: &Self) -> Self;
     fn scale(self, scalar: U) -> Self;
 }
+
+/// Multiplies a column-major 4x4 matrix with a column vector.
+pub fn transform(matrix: &[f32; 16], vector: [f32; 4]) -> [f32; 4] {
+    let mut result = [0.0; 4];
+    for (row, value) in result.iter_mut().enumerate() {
+        *value = (0..4).map(|col| matrix[col * 4 + row] * vector[col]).sum();
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn test_get_max_skips_nan() {
+        assert_eq!(Vec3::from([f32::NAN, 2.0, 1.0]).get_max(), 2.0);
+        assert_eq!(Vec3::from([1.0, f32::NAN, 3.0]).get_max(), 3.0);
+    }
+
+    #[test]
+    fn test_bounding_box() {
+        let bounds = BoundingBox::from_points([[-130.0, -30.0, 0.0], [30.0, 30.0, 50.0]]).unwrap();
+
+        assert_eq!(bounds.center(), Vec3::from([-50.0, 0.0, 25.0]));
+        assert_eq!(bounds.extent(), Vec3::from([160.0, 60.0, 50.0]));
+        assert_eq!(bounds.diagonal(), 178.04494);
+        assert_eq!(bounds.bounding_sphere().center, bounds.center());
+        assert_eq!(bounds.bounding_sphere().radius, 89.02247);
+        assert_eq!(BoundingBox::from_points([[0.0, f32::NAN, 0.0]]), None);
+        assert_eq!(BoundingBox::from_points([]), None);
+    }
+
+    #[test]
+    fn test_sphere_union() {
+        let unit = Sphere::default();
+        let inner = Sphere {
+            center: Vec3::from([0.5, 0.0, 0.0]),
+            radius: 0.25,
+        };
+        let apart = Sphere {
+            center: Vec3::from([4.0, 0.0, 0.0]),
+            radius: 2.0,
+        };
+        assert_eq!(unit.union(&inner), unit);
+        assert_eq!(inner.union(&unit), unit);
+        assert_eq!(unit.union(&apart).radius, 3.5);
+        assert_close(unit.union(&apart).center, [2.5, 0.0, 0.0]);
+    }
+
+    fn assert_close(lhs: Vec3<f32>, rhs: [f32; 3]) {
+        for (lhs, rhs) in lhs.0.into_iter().zip(rhs) {
+            assert!((lhs - rhs).abs() < 1e-6, "{:?} != {:?}", lhs, rhs);
+        }
+    }
+
+    #[test]
+    fn test_quaternion_rotation() {
+        let x = Vec3::from([1.0, 0.0, 0.0]);
+        let y = Vec3::from([0.0, 1.0, 0.0]);
+        let quarter = Quaternion::from_axis_angle(Vec3::from([0.0, 0.0, 2.0]), PI / 2.0);
+        assert_close(quarter.rotate(x), [0.0, 1.0, 0.0]);
+        assert_close((quarter * quarter).rotate(x), [-1.0, 0.0, 0.0]);
+        assert_close((quarter * quarter.conjugate()).rotate(y), [0.0, 1.0, 0.0]);
+        assert_close(
+            Quaternion::from_rotation_arc(x, y).rotate(x),
+            [0.0, 1.0, 0.0],
+        );
+
+        let matrix = quarter.to_matrix();
+        assert_close(
+            Vec3::from([matrix[0], matrix[1], matrix[2]]),
+            [0.0, 1.0, 0.0],
+        );
+        assert_close(
+            Vec3::from([matrix[4], matrix[5], matrix[6]]),
+            [-1.0, 0.0, 0.0],
+        );
+    }
+
+    #[test]
+    fn test_quaternion_slerp() {
+        let axis = Vec3::from([0.0, 0.0, 1.0]);
+        let from = Quaternion::from_axis_angle(axis, 0.2);
+        let to = Quaternion::from_axis_angle(axis, 1.8);
+        assert_eq!(from.slerp(to, 0.0), from);
+        assert_close(from.slerp(to, 1.0).v, to.v.into());
+        assert_close(
+            from.slerp(to, 0.25).v,
+            Quaternion::from_axis_angle(axis, 0.6).v.into(),
+        );
+        // The long way round is never taken.
+        let negated = to.scale(-1.0);
+        assert_close(from.slerp(negated, 0.5).to_scaled_axis(), [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_quaternion_scaled_axis() {
+        let axis = Vec3::from([0.0, 0.3, 0.0]);
+        assert_close(
+            Quaternion::from_scaled_axis(axis).to_scaled_axis(),
+            [0.0, 0.3, 0.0],
+        );
+        // The negated quaternion is the same rotation.
+        let negated = Quaternion::from_scaled_axis(axis).scale(-1.0);
+        assert_close(negated.to_scaled_axis(), [0.0, 0.3, 0.0]);
+        assert_close(Quaternion::IDENTITY.to_scaled_axis(), [0.0; 3]);
+    }
+}
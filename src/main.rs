@@ -1,326 +1,251 @@
-use constants::{AMORTIZATION, COMPONENTS_PER_VERTEX, FIELD_OF_VIEW, Z_FAR, Z_NEAR};
-use js_sys::{Uint8Array, WebAssembly};
-use std::cell::RefCell;
-use std::rc::Rc;
-use utils::{compile_shader, link_program, request_animation_frame, resize_canvas, window};
-use wasm_bindgen::prelude::*;
-use web_sys::{
-    console, Event, FileReader, HtmlDivElement, HtmlInputElement, WebGlBuffer, WebGlProgram,
-    WebGlRenderingContext, WebGlUniformLocation,
-};
-
-mod constants;
-mod event_handlers;
-mod linalg;
-mod stl;
-mod utils;
-
-#[derive(Debug, Clone)]
-struct ProgramInfo(
-    WebGlProgram,
-    u32,
-    (
-        Result<WebGlUniformLocation, String>,
-        Result<WebGlUniformLocation, String>,
-    ),
-);
-
-#[derive(Debug, Clone)]
-struct Buffers(WebGlBuffer, WebGlBuffer);
-
-fn main() {
-    set_panic_hook();
-    set_file_reader().unwrap()
+use std::error::Error;
+use std::path::Path;
+use std::process::ExitCode;
+
+use wedge::keymap::Action;
+use wedge::linalg::Sphere;
+use wedge::stl::{self, StlFile, StlFormat};
+use wedge::view::{StandardView, ViewerState};
+use wedge::{analysis, export, render};
+
+const USAGE: &str = "Usage: wedge <command> [arguments]
+
+Commands:
+  info <file>                              Print header, facet count, bounding box,
+                                           surface area and volume
+  validate <file>...                       Fail if a file is malformed, empty, not
+                                           manifold or has degenerate triangles
+  convert <input> <output> [--ascii|--binary]
+                                           Convert to STL, OBJ or PLY, chosen by the
+                                           extension of <output>
+  thumbnail <file> [-o <output>] [--size <pixels>]
+                                           Render an isometric view to a square PNG,
+                                           <file> with a .png extension and 256 pixels
+                                           by default, at most 4096 pixels";
+
+const THUMBNAIL_SIZE: u32 = 256;
+/// Rendering takes about 80 bytes per pixel, 1.3 GiB at this size.
+const MAX_THUMBNAIL_SIZE: u32 = 4096;
+
+/// Vertices closer than this are treated as shared when checking topology.
+const WELD_TOLERANCE: f32 = 1e-5;
+
+#[derive(Debug)]
+enum Command {
+    Info(String),
+    Validate(Vec<String>),
+    Convert {
+        input: String,
+        output: String,
+        format: Option<StlFormat>,
+    },
+    Thumbnail {
+        input: String,
+        output: String,
+        size: u32,
+    },
 }
 
-fn set_file_reader() -> Result<(), JsValue> {
-    let document = window()
-        .document()
-        .expect("should have a document on window");
-    let file_in_div = document.get_element_by_id("file-input-div").unwrap();
-    let file_in_div: HtmlDivElement = file_in_div.dyn_into()?;
-
-    let fileinput: HtmlInputElement = document
-        .create_element("input")?
-        .dyn_into::<HtmlInputElement>()?;
-
-    fileinput.set_id("file-input");
-    fileinput.set_class_name("file-input");
-    fileinput.set_type("file");
-
-    let filereader = FileReader::new()?;
-
-    let closure = Closure::wrap(Box::new(move |event: Event| {
-        let element = event.target().unwrap().dyn_into::<FileReader>().unwrap();
-        let buffer = Uint8Array::new(&element.result().unwrap());
-        let v = buffer.to_vec();
-
-        match stl::get_data(&v) {
-            Ok((vertices, num_vertices)) => render(vertices, num_vertices).unwrap(),
-            Err(e) => console::log_1(&format!("The given file is corrupted: Error: {}", e).into()),
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match parse_args(&args) {
+        Some(command) => command,
+        None => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
         }
-    }) as Box<dyn FnMut(_)>);
-
-    filereader.set_onloadend(Some(closure.as_ref().unchecked_ref()));
-    closure.forget();
-
-    let closure = Closure::wrap(Box::new(move |event: Event| {
-        let element = event
-            .target()
-            .unwrap()
-            .dyn_into::<HtmlInputElement>()
-            .unwrap();
-        let filelist = element.files().unwrap();
-        let file = filelist.get(0).expect("should have a file handle.");
-        filereader.read_as_array_buffer(&file).unwrap();
-    }) as Box<dyn FnMut(_)>);
-
-    fileinput.add_event_listener_with_callback("change", closure.as_ref().unchecked_ref())?;
-    closure.forget();
-
-    file_in_div.append_child(&fileinput)?;
-    Ok(())
-}
-
-fn render(vertices: Vec<f32>, num_vertices: u32) -> Result<(), JsValue> {
-    let document = window()
-        .document()
-        .expect("should have a document on window");
-
-    let canvas = document.get_element_by_id("canvas").unwrap();
-    let canvas: web_sys::HtmlCanvasElement = canvas.dyn_into::<web_sys::HtmlCanvasElement>()?;
-
-    let gl = canvas
-        .get_context("webgl")?
-        .unwrap()
-        .dyn_into::<WebGlRenderingContext>()?;
-
-    gl.clear_color(0.375, 0.375, 0.375, 1.0);
-    gl.clear_depth(1.0);
-    gl.enable(WebGlRenderingContext::DEPTH_TEST);
+    };
 
-    let vertex_shader_source = r#"
-        attribute vec4 aVertexPosition;
-        uniform mat4 uModelViewMatrix;
-        uniform mat4 uProjectionMatrix;
+    let result = match command {
+        Command::Info(path) => info(&path),
+        Command::Validate(paths) => validate(&paths),
+        Command::Convert {
+            input,
+            output,
+            format,
+        } => convert(&input, &output, format),
+        Command::Thumbnail {
+            input,
+            output,
+            size,
+        } => thumbnail(&input, &output, size),
+    };
 
-        varying lowp vec4 vColor;
-        
-        void main(void) {
-            gl_Position = uProjectionMatrix * uModelViewMatrix * aVertexPosition;
-            vColor = aVertexPosition;
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("wedge: {}", e);
+            ExitCode::FAILURE
         }
-    "#;
+    }
+}
 
-    let fragment_shader_source = r#"
-        varying lowp vec4 vColor;
-        void main(void) {
-            gl_FragColor = vColor;
+fn parse_args(args: &[String]) -> Option<Command> {
+    let (command, args) = args.split_first()?;
+    match command.as_str() {
+        "info" if args.len() == 1 => Some(Command::Info(args[0].clone())),
+        "validate" if !args.is_empty() => Some(Command::Validate(args.to_vec())),
+        "convert" => {
+            let mut paths = vec![];
+            let mut format = None;
+            for arg in args {
+                match arg.as_str() {
+                    "--ascii" => format = Some(StlFormat::Ascii),
+                    "--binary" => format = Some(StlFormat::Binary),
+                    _ if arg.starts_with("--") => return None,
+                    _ => paths.push(arg.clone()),
+                }
+            }
+            let [input, output]: [String; 2] = paths.try_into().ok()?;
+            Some(Command::Convert {
+                input,
+                output,
+                format,
+            })
         }
-    "#;
-
-    let shader_program = init_shader_program(&gl, vertex_shader_source, fragment_shader_source)?;
-
-    let programm_info = {
-        let vertex_pos = gl.get_attrib_location(&shader_program, "aVertexPosition") as u32;
-        let projection_matrix = gl
-            .get_uniform_location(&shader_program, "uProjectionMatrix")
-            .ok_or_else(|| String::from("cannot get uProjectionMatrix"));
-        let model_view_matrix = gl
-            .get_uniform_location(&shader_program, "uModelViewMatrix")
-            .ok_or_else(|| String::from("cannot get uModelViewMatrix"));
-        ProgramInfo(
-            shader_program,
-            vertex_pos,
-            (projection_matrix, model_view_matrix),
-        )
-    };
-
-    let buffers: Buffers = init_buffers(&gl, vertices, num_vertices)?;
-
-    let f = Rc::new(RefCell::new(None));
-    let g = f.clone();
-    let zoom = Rc::new(RefCell::new(-5.0));
-    let drag = Rc::new(RefCell::new(false));
-    let theta = Rc::new(RefCell::new(0.0));
-    let phi = Rc::new(RefCell::new(0.0));
-    let dx = Rc::new(RefCell::new(0.0));
-    let dy = Rc::new(RefCell::new(0.0));
-
-    event_handlers::set_event_handlers(
-        canvas.clone(),
-        zoom.clone(),
-        drag.clone(),
-        theta.clone(),
-        phi.clone(),
-        dx.clone(),
-        dy.clone(),
-    );
-
-    resize_canvas(canvas.clone());
-
-    gl.get_extension("OES_element_index_uint").unwrap();
-
-    *g.borrow_mut() = Some(Closure::wrap(Box::new(move |_d| {
-        if !*drag.borrow() {
-            *dx.borrow_mut() *= AMORTIZATION;
-            *dy.borrow_mut() *= AMORTIZATION;
-            *theta.borrow_mut() += *dx.borrow();
-            *phi.borrow_mut() += *dy.borrow();
+        "thumbnail" => {
+            let mut input = None;
+            let mut output = None;
+            let mut size = THUMBNAIL_SIZE;
+            let mut args = args.iter();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "-o" | "--output" => output = Some(args.next()?.clone()),
+                    "--size" => {
+                        size = args
+                            .next()?
+                            .parse()
+                            .ok()
+                            .filter(|size| (1..=MAX_THUMBNAIL_SIZE).contains(size))?
+                    }
+                    _ if arg.starts_with('-') || input.is_some() => return None,
+                    _ => input = Some(arg.clone()),
+                }
+            }
+            let input = input?;
+            let output = output.unwrap_or_else(|| {
+                Path::new(&input)
+                    .with_extension("png")
+                    .to_string_lossy()
+                    .into_owned()
+            });
+            Some(Command::Thumbnail {
+                input,
+                output,
+                size,
+            })
         }
-        draw_scene(
-            &gl.clone(),
-            programm_info.clone(),
-            buffers.clone(),
-            *zoom.borrow(),
-            *theta.borrow(),
-            *phi.borrow(),
-            num_vertices,
-            &canvas,
-        )
-        .unwrap();
-        request_animation_frame(f.borrow().as_ref().unwrap());
-    }) as Box<dyn FnMut(f32)>));
-
-    request_animation_frame(g.borrow().as_ref().unwrap());
-
-    Ok(())
+        _ => None,
+    }
 }
 
-fn init_shader_program(
-    gl: &WebGlRenderingContext,
-    vs_source: &str,
-    fs_source: &str,
-) -> Result<WebGlProgram, String> {
-    let v_shader = compile_shader(gl, WebGlRenderingContext::VERTEX_SHADER, vs_source);
-    let f_shader = compile_shader(gl, WebGlRenderingContext::FRAGMENT_SHADER, fs_source);
-
-    link_program(gl, &v_shader?, &f_shader?)
+fn read(path: &str) -> Result<StlFile, Box<dyn Error>> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    Ok(stl::parse(&bytes).map_err(|e| format!("{}: {}", path, e))?)
 }
 
-fn init_buffers(
-    gl: &WebGlRenderingContext,
-    vertices: Vec<f32>,
-    num_vertices: u32,
-) -> Result<Buffers, JsValue> {
-    let position_buffer = gl
-        .create_buffer()
-        .ok_or("failed to create positionBuffer buffer")?;
-
-    gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&position_buffer));
-
-    let position_array = float_32_array!(vertices);
-    gl.buffer_data_with_array_buffer_view(
-        WebGlRenderingContext::ARRAY_BUFFER,
-        &position_array,
-        WebGlRenderingContext::STATIC_DRAW,
-    );
-
-    let index_buffer = gl
-        .create_buffer()
-        .ok_or("failed to create indexBuffer buffer")?;
-    gl.bind_buffer(
-        WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
-        Some(&index_buffer),
-    );
-
-    let indices: Vec<u32> = (0..num_vertices).collect();
-
-    let index_array = uint_32_array!(indices);
-    gl.buffer_data_with_array_buffer_view(
-        WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
-        &index_array,
-        WebGlRenderingContext::STATIC_DRAW,
-    );
-    Ok(Buffers(position_buffer, index_buffer))
+fn info(path: &str) -> Result<ExitCode, Box<dyn Error>> {
+    let file = read(path)?;
+    let mesh = file.to_mesh(WELD_TOLERANCE);
+
+    println!("file:         {}", path);
+    println!("format:       {:?}", file.format);
+    println!("header:       {}", file.header);
+    println!("facets:       {}", file.num_facets());
+    println!("vertices:     {}", mesh.positions.len() / 3);
+    if let Some(bounds) = mesh.bounds() {
+        let [min, max, size]: [[f32; 3]; 3] =
+            [bounds.min, bounds.max, bounds.extent()].map(Into::into);
+        println!("bounding box: min {:?} max {:?}", min, max);
+        println!("size:         {:?}", size);
+        println!("diagonal:     {}", bounds.diagonal());
+    }
+    println!("surface area: {}", analysis::surface_area(&mesh));
+    println!("volume:       {}", analysis::volume(&mesh));
+
+    Ok(ExitCode::SUCCESS)
 }
 
-#[allow(clippy::too_many_arguments)]
-fn draw_scene(
-    gl: &WebGlRenderingContext,
-    program_info: ProgramInfo,
-    buffers: Buffers,
-    zoom: f32,
-    theta: f32,
-    phi: f32,
-    num_vertices: u32,
-    canvas: &web_sys::HtmlCanvasElement,
-) -> Result<(), JsValue> {
-    let Buffers(position_buffer, index_buffer) = buffers;
-    let ProgramInfo(
-        shader_program,
-        vertex_position,
-        (location_projection_matrix, location_model_view_matrix),
-    ) = program_info;
-
-    gl.clear(WebGlRenderingContext::COLOR_BUFFER_BIT | WebGlRenderingContext::DEPTH_BUFFER_BIT);
-    gl.viewport(0, 0, canvas.width() as i32, canvas.height() as i32);
-
-    let aspect_ratio: f32 = canvas.width() as f32 / canvas.height() as f32;
-    let mut projection_matrix = mat4::new_zero();
-
-    mat4::perspective(
-        &mut projection_matrix,
-        &FIELD_OF_VIEW,
-        &aspect_ratio,
-        &Z_NEAR,
-        &Z_FAR,
-    );
-
-    let mut model_view_matrix = mat4::new_identity();
-
-    let mat_to_translate = model_view_matrix;
-    mat4::translate(&mut model_view_matrix, &mat_to_translate, &[0.0, 0.0, zoom]);
-
-    let mat_to_rotate = model_view_matrix;
-    mat4::rotate_x(&mut model_view_matrix, &mat_to_rotate, &phi);
-    let mat_to_rotate = model_view_matrix;
-    mat4::rotate_y(&mut model_view_matrix, &mat_to_rotate, &theta);
-
-    gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&position_buffer));
-    gl.vertex_attrib_pointer_with_i32(
-        vertex_position,
-        COMPONENTS_PER_VERTEX,
-        WebGlRenderingContext::FLOAT,
-        false,
-        0,
-        0,
-    );
-
-    gl.enable_vertex_attrib_array(vertex_position);
-
-    gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, None);
-
-    gl.bind_buffer(
-        WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
-        Some(&index_buffer),
-    );
+fn validate(paths: &[String]) -> Result<ExitCode, Box<dyn Error>> {
+    let mut code = ExitCode::SUCCESS;
+
+    for path in paths {
+        let file = match read(path) {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!("{}", e);
+                code = ExitCode::FAILURE;
+                continue;
+            }
+        };
+        let topology = analysis::topology(&file.to_mesh(WELD_TOLERANCE));
+        let mut problems = vec![];
+        if file.num_facets() == 0 {
+            problems.push("no facets".to_string());
+        }
+        if !topology.is_manifold() {
+            problems.push(format!(
+                "not manifold, {} boundary, {} non-manifold and {} inconsistently oriented edges",
+                topology.boundary_edges, topology.non_manifold_edges, topology.inconsistent_edges
+            ));
+        }
+        if topology.degenerate_triangles > 0 {
+            problems.push(format!(
+                "{} degenerate triangles",
+                topology.degenerate_triangles
+            ));
+        }
+        if problems.is_empty() {
+            println!("{}: ok", path);
+        } else {
+            eprintln!("{}: {}", path, problems.join("; "));
+            code = ExitCode::FAILURE;
+        }
+    }
 
-    gl.use_program(Some(&shader_program));
+    Ok(code)
+}
 
-    gl.uniform_matrix4fv_with_f32_array(
-        Some(&location_projection_matrix?),
-        false,
-        &projection_matrix,
-    );
+fn convert(
+    input: &str,
+    output: &str,
+    format: Option<StlFormat>,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let file = read(input)?;
+    let extension = Path::new(output)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+
+    let bytes = match extension.as_deref() {
+        Some("stl") => match format.unwrap_or(StlFormat::Binary) {
+            StlFormat::Ascii => stl::write_ascii(&file).into_bytes(),
+            StlFormat::Binary => stl::write_binary(&file),
+        },
+        Some("obj") => export::to_obj(&file.to_mesh(WELD_TOLERANCE)).into_bytes(),
+        Some("ply") => export::to_ply(&file.to_mesh(WELD_TOLERANCE)).into_bytes(),
+        _ => return Err(format!("{}: unsupported output format", output).into()),
+    };
+    std::fs::write(output, bytes).map_err(|e| format!("{}: {}", output, e))?;
 
-    gl.uniform_matrix4fv_with_f32_array(
-        Some(&location_model_view_matrix?),
-        false,
-        &model_view_matrix,
-    );
+    Ok(ExitCode::SUCCESS)
+}
 
-    gl.draw_elements_with_i32(
-        WebGlRenderingContext::TRIANGLES,
-        num_vertices as i32,
-        WebGlRenderingContext::UNSIGNED_INT,
-        0,
+fn thumbnail(input: &str, output: &str, size: u32) -> Result<ExitCode, Box<dyn Error>> {
+    let mesh = read(input)?.to_mesh(WELD_TOLERANCE);
+    let mut state = ViewerState {
+        transition_duration: 0.0,
+        ..ViewerState::default()
+    };
+    state.set_scene(
+        mesh.bounds()
+            .map_or_else(Sphere::default, |bounds| bounds.bounding_sphere()),
     );
+    state.apply(Action::View(StandardView::Iso));
 
-    Ok(())
-}
+    let image = render::render(&mesh, &state, size, size, render::BACKGROUND_COLOR)?;
+    let png = image.to_png().map_err(|e| format!("{}: {}", output, e))?;
+    std::fs::write(output, png).map_err(|e| format!("{}: {}", output, e))?;
 
-fn set_panic_hook() {
-    #[cfg(feature = "console_error_panic_hook")]
-    console_error_panic_hook::set_once();
+    Ok(ExitCode::SUCCESS)
 }
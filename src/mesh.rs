@@ -0,0 +1,478 @@
+use std::collections::HashMap;
+
+use crate::linalg::{BoundingBox, Vec3, VecOps};
+
+const AXES: usize = 3;
+const VERTICES_PER_TRIANGLE: usize = 3;
+const NEIGHBOUR_CELLS: [i64; 3] = [-1, 0, 1];
+/// RGB colour of triangles without one of their own.
+pub const DEFAULT_COLOR: [f32; 3] = [0.70, 0.72, 0.76];
+
+/// Triangle mesh with shared vertices.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Mesh {
+    /// Flat `x, y, z` coordinates of the unique vertices.
+    pub positions: Vec<f32>,
+    /// Three indices into `positions` per triangle.
+    pub indices: Vec<u32>,
+    /// Flat `x, y, z` unit normal per triangle.
+    pub normals: Vec<f32>,
+    /// Flat `r, g, b` colour between 0 and 1 per triangle, empty if the mesh
+    /// is not coloured.
+    pub colors: Vec<f32>,
+}
+
+/// Vertex data ready to be uploaded to the GPU, one normal per vertex.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShadedMesh {
+    pub positions: Vec<f32>,
+    pub normals: Vec<f32>,
+    /// Flat `r, g, b` colour per vertex, empty if the mesh is not coloured.
+    pub colors: Vec<f32>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Welds a triangle soup, i.e. three `x, y, z` triples per triangle, into
+    /// an indexed mesh. Vertices closer than `tolerance` are merged, a
+    /// tolerance of zero only merges bitwise identical coordinates.
+    ///
+    /// `normals` holds one normal per triangle as stored in the file; normals
+    /// which are zero, not finite or contradict the winding are recomputed.
+    pub fn from_triangles(vertices: &[f32], normals: &[f32], tolerance: f32) -> Self {
+        let mut welder = Welder::new(tolerance);
+        let indices = vertices
+            .chunks_exact(AXES)
+            .map(|v| welder.insert([v[0], v[1], v[2]]))
+            .collect();
+        let normals = vertices
+            .chunks_exact(VERTICES_PER_TRIANGLE * AXES)
+            .zip(normals.chunks_exact(AXES))
+            .flat_map(|(triangle, normal)| facet_normal(triangle, normal))
+            .collect();
+
+        Self {
+            positions: welder.positions,
+            indices,
+            normals,
+            colors: vec![],
+        }
+    }
+
+    /// Adds the triangles of `other` without welding them to the existing ones.
+    /// If only one of them is coloured, the other gets the default colour.
+    pub fn append(&mut self, other: &Mesh) {
+        if self.colors.is_empty() != other.colors.is_empty() {
+            self.colors = self.triangle_colors().collect();
+            self.colors.extend(other.triangle_colors());
+        } else {
+            self.colors.extend_from_slice(&other.colors);
+        }
+        let offset = (self.positions.len() / AXES) as u32;
+        self.positions.extend_from_slice(&other.positions);
+        self.indices
+            .extend(other.indices.iter().map(|idx| idx + offset));
+        self.normals.extend_from_slice(&other.normals);
+    }
+
+    /// Flat `r, g, b` colour of every triangle, the default one if the mesh
+    /// is not coloured.
+    pub fn triangle_colors(&self) -> impl Iterator<Item = f32> + '_ {
+        let num_triangles = self.normals.len() / AXES;
+        let num_defaults = if self.colors.is_empty() {
+            num_triangles
+        } else {
+            0
+        };
+        let default = std::iter::repeat_n(DEFAULT_COLOR, num_defaults).flatten();
+        self.colors.iter().copied().chain(default)
+    }
+
+    /// Splits every vertex per triangle so each triangle is lit with its own
+    /// facet normal.
+    pub fn flat_shaded(&self) -> ShadedMesh {
+        let mut shaded = ShadedMesh {
+            positions: Vec::with_capacity(self.indices.len() * AXES),
+            normals: Vec::with_capacity(self.indices.len() * AXES),
+            colors: Vec::with_capacity(self.colors.len() * VERTICES_PER_TRIANGLE),
+            indices: (0..self.indices.len() as u32).collect(),
+        };
+        for (triangle_idx, (triangle, normal)) in self
+            .indices
+            .chunks_exact(VERTICES_PER_TRIANGLE)
+            .zip(self.normals.chunks_exact(AXES))
+            .enumerate()
+        {
+            for &idx in triangle {
+                let idx = idx as usize * AXES;
+                shaded
+                    .positions
+                    .extend_from_slice(&self.positions[idx..idx + AXES]);
+                shaded.normals.extend_from_slice(normal);
+                shaded.colors.extend_from_slice(self.color(triangle_idx));
+            }
+        }
+        shaded
+    }
+
+    /// Averages the normals of the triangles around every vertex, weighted by
+    /// their area. Triangles whose normals differ by more than `crease_angle`
+    /// radians do not contribute to each other, so sharp edges stay crisp.
+    pub fn smooth_shaded(&self, crease_angle: f32) -> ShadedMesh {
+        let cos_crease = crease_angle.cos();
+        let triangles: Vec<&[u32]> = self.indices.chunks_exact(VERTICES_PER_TRIANGLE).collect();
+        let normals: Vec<Vec3<f32>> = self
+            .normals
+            .chunks_exact(AXES)
+            .map(|n| Vec3::from([n[0], n[1], n[2]]))
+            .collect();
+        let areas: Vec<f32> = triangles
+            .iter()
+            .map(|triangle| {
+                let [a, b, c] = [0, 1, 2].map(|idx| self.position(triangle[idx]));
+                (b - a).cross(&(c - a)).length() * 0.5
+            })
+            .collect();
+
+        let mut incident: Vec<Vec<usize>> = vec![vec![]; self.positions.len() / AXES];
+        for (triangle_idx, triangle) in triangles.iter().enumerate() {
+            for &idx in triangle.iter() {
+                incident[idx as usize].push(triangle_idx);
+            }
+        }
+
+        let mut shaded = ShadedMesh::default();
+        let mut corners: HashMap<(u32, [u32; 3], [u32; 3]), u32> = HashMap::new();
+        for (triangle_idx, triangle) in triangles.iter().enumerate() {
+            let normal = normals[triangle_idx];
+            let color = self.color(triangle_idx);
+            let color_bits = match color {
+                [r, g, b] => [*r, *g, *b].map(f32::to_bits),
+                _ => [0; 3],
+            };
+            for &idx in triangle.iter() {
+                let smooth = incident[idx as usize]
+                    .iter()
+                    .filter(|&&other| normals[other].dot(&normal) >= cos_crease)
+                    .fold(Vec3::new(0.0), |sum, &other| {
+                        sum + normals[other].scale(areas[other])
+                    })
+                    .normalize();
+                let smooth: [f32; 3] = if smooth.length() > 0.0 {
+                    smooth.into()
+                } else {
+                    normal.into()
+                };
+
+                // Corners ending up with the same normal and colour share a
+                // vertex.
+                let next = (shaded.positions.len() / AXES) as u32;
+                let shaded_idx = *corners
+                    .entry((idx, smooth.map(f32::to_bits), color_bits))
+                    .or_insert_with(|| {
+                        let position: [f32; 3] = self.position(idx).into();
+                        shaded.positions.extend_from_slice(&position);
+                        shaded.normals.extend_from_slice(&smooth);
+                        shaded.colors.extend_from_slice(color);
+                        next
+                    });
+                shaded.indices.push(shaded_idx);
+            }
+        }
+        shaded
+    }
+
+    /// Two indices per unique triangle edge, for drawing a wireframe.
+    pub fn edges(&self) -> Vec<u32> {
+        self.edge_triangles()
+            .into_iter()
+            .flat_map(|((a, b), _)| [a, b])
+            .collect()
+    }
+
+    /// Two indices per edge where the surface bends by more than `angle`
+    /// radians, plus the edges of holes and non-manifold edges, which
+    /// together outline the shape of CAD parts.
+    pub fn feature_edges(&self, angle: f32) -> Vec<u32> {
+        let cos_angle = angle.cos();
+        self.edge_triangles()
+            .into_iter()
+            .filter(|(_, triangles)| match triangles[..] {
+                [a, b] => self.facet(a).dot(&self.facet(b)) < cos_angle,
+                _ => true,
+            })
+            .flat_map(|((a, b), _)| [a, b])
+            .collect()
+    }
+
+    /// Every unique edge, in order of first appearance, with the triangles
+    /// it belongs to.
+    fn edge_triangles(&self) -> Vec<((u32, u32), Vec<usize>)> {
+        let mut lookup = HashMap::new();
+        let mut edges: Vec<((u32, u32), Vec<usize>)> = vec![];
+        for (triangle, corners) in self.indices.chunks_exact(VERTICES_PER_TRIANGLE).enumerate() {
+            for corner in 0..VERTICES_PER_TRIANGLE {
+                let a = corners[corner];
+                let b = corners[(corner + 1) % VERTICES_PER_TRIANGLE];
+                let idx = *lookup.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    edges.push(((a, b), vec![]));
+                    edges.len() - 1
+                });
+                edges[idx].1.push(triangle);
+            }
+        }
+        edges
+    }
+
+    /// Colour of a triangle, empty if the mesh is not coloured.
+    fn color(&self, triangle: usize) -> &[f32] {
+        let idx = triangle * AXES;
+        self.colors.get(idx..idx + AXES).unwrap_or(&[])
+    }
+
+    fn facet(&self, triangle: usize) -> Vec3<f32> {
+        let idx = triangle * AXES;
+        Vec3::from([
+            self.normals[idx],
+            self.normals[idx + 1],
+            self.normals[idx + 2],
+        ])
+    }
+
+    /// Returns `None` for an empty mesh.
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        BoundingBox::from_points(
+            self.positions
+                .chunks_exact(AXES)
+                .map(|v| [v[0], v[1], v[2]]),
+        )
+    }
+
+    pub fn position(&self, idx: u32) -> Vec3<f32> {
+        let idx = idx as usize * AXES;
+        Vec3::from([
+            self.positions[idx],
+            self.positions[idx + 1],
+            self.positions[idx + 2],
+        ])
+    }
+}
+
+fn facet_normal(triangle: &[f32], stored: &[f32]) -> [f32; 3] {
+    let [a, b, c] = [0, 1, 2].map(|idx| {
+        let idx = idx * AXES;
+        Vec3::from([triangle[idx], triangle[idx + 1], triangle[idx + 2]])
+    });
+    let computed = (b - a).cross(&(c - a)).normalize();
+    let stored = Vec3::from([stored[0], stored[1], stored[2]]);
+
+    let is_valid = stored.length().is_finite()
+        && stored.length() > 0.0
+        && (computed.length() == 0.0 || stored.dot(&computed) > 0.0);
+    if is_valid {
+        stored.normalize().into()
+    } else {
+        computed.into()
+    }
+}
+
+/// Spatial hash over cells of the size of the weld tolerance, so only the
+/// neighbouring cells need to be searched for a match.
+struct Welder {
+    tolerance: f32,
+    cells: HashMap<[i64; 3], Vec<u32>>,
+    positions: Vec<f32>,
+}
+
+impl Welder {
+    fn new(tolerance: f32) -> Self {
+        Self {
+            tolerance,
+            cells: HashMap::new(),
+            positions: vec![],
+        }
+    }
+
+    fn cell(&self, v: [f32; 3]) -> [i64; 3] {
+        if self.tolerance > 0.0 {
+            v.map(|c| (c / self.tolerance).floor() as i64)
+        } else {
+            // Adding zero folds -0.0 into 0.0.
+            v.map(|c| (c + 0.0).to_bits() as i64)
+        }
+    }
+
+    fn find(&self, cell: [i64; 3], v: [f32; 3]) -> Option<u32> {
+        let candidates = self.cells.get(&cell)?;
+        candidates.iter().copied().find(|&idx| {
+            let idx = idx as usize * AXES;
+            let distance: f32 = (0..AXES)
+                .map(|axis| (self.positions[idx + axis] - v[axis]).powi(2))
+                .sum();
+            distance <= self.tolerance * self.tolerance
+        })
+    }
+
+    fn insert(&mut self, v: [f32; 3]) -> u32 {
+        let cell = self.cell(v);
+        if self.tolerance > 0.0 {
+            for dx in NEIGHBOUR_CELLS {
+                for dy in NEIGHBOUR_CELLS {
+                    for dz in NEIGHBOUR_CELLS {
+                        // Huge coordinates saturate the cell index.
+                        let neighbour = [
+                            cell[0].saturating_add(dx),
+                            cell[1].saturating_add(dy),
+                            cell[2].saturating_add(dz),
+                        ];
+                        if let Some(idx) = self.find(neighbour, v) {
+                            return idx;
+                        }
+                    }
+                }
+            }
+        } else if let Some(idx) = self.find(cell, v) {
+            return idx;
+        }
+
+        let idx = (self.positions.len() / AXES) as u32;
+        self.positions.extend_from_slice(&v);
+        self.cells.entry(cell).or_default().push(idx);
+        idx
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn test_weld_tolerance() {
+        let vertices = [
+            0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, //
+            1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0001, 0.0,
+        ];
+
+        let normals = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0];
+
+        let exact = Mesh::from_triangles(&vertices, &normals, 0.0);
+        let welded = Mesh::from_triangles(&vertices, &normals, 0.001);
+
+        assert_eq!(exact.positions.len(), 5 * AXES);
+        assert_eq!(welded.positions.len(), 4 * AXES);
+        assert_eq!(welded.indices, vec![0, 1, 2, 1, 3, 2]);
+    }
+
+    #[test]
+    fn test_weld_huge_coordinates() {
+        let vertices = [
+            1e20,
+            0.0,
+            0.0,
+            -1e20,
+            1.0,
+            0.0,
+            0.0,
+            f32::MAX,
+            0.0, //
+            1e20,
+            0.0,
+            0.0,
+            -1e20,
+            1.0,
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+        ];
+        let normals = [0.0; 6];
+
+        let welded = Mesh::from_triangles(&vertices, &normals, 1e-5);
+
+        assert_eq!(welded.positions.len(), 4 * AXES);
+    }
+
+    #[test]
+    fn test_broken_normals_are_recomputed() {
+        let vertices = [
+            0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, //
+            0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, //
+            0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0,
+        ];
+        let normals = [0.0, 0.0, 2.0, 0.0, 0.0, 1.0, f32::NAN, 0.0, 0.0];
+
+        let mesh = Mesh::from_triangles(&vertices, &normals, 0.0);
+
+        assert_eq!(
+            mesh.normals,
+            vec![0.0, 0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 0.0, 1.0]
+        );
+        assert_eq!(mesh.flat_shaded().normals.len(), vertices.len());
+    }
+
+    #[test]
+    fn test_smooth_shading_keeps_creases() {
+        let cube = std::fs::read("tests/files/cube.stl").unwrap();
+        let mesh = crate::stl::get_data(&cube, 0.0).unwrap();
+
+        let sharp = mesh.smooth_shaded(30.0_f32.to_radians());
+        let smooth = mesh.smooth_shaded(100.0_f32.to_radians());
+
+        // Every corner of a cube touches three faces.
+        assert_eq!(sharp.positions.len(), 8 * 3 * AXES);
+        assert_eq!(smooth.positions.len(), 8 * AXES);
+        assert!(sharp
+            .normals
+            .iter()
+            .all(|n| n.abs() == 0.0 || n.abs() == 1.0));
+    }
+
+    #[test]
+    fn test_colors() {
+        let vertices = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let plain = Mesh::from_triangles(&vertices, &[0.0, 0.0, 1.0], 0.0);
+        let mut colored = plain.clone();
+        colored.colors = vec![1.0, 0.0, 0.0];
+
+        assert_eq!(colored.flat_shaded().colors.len(), 3 * 3);
+        assert_eq!(colored.smooth_shaded(0.0).colors[..3], [1.0, 0.0, 0.0]);
+        assert!(plain.flat_shaded().colors.is_empty());
+
+        // The uncoloured part takes the default colour.
+        let mut scene = plain.clone();
+        scene.append(&colored);
+        assert_eq!(scene.colors[..3], DEFAULT_COLOR);
+        assert_eq!(scene.colors[3..], [1.0, 0.0, 0.0]);
+        scene.append(&plain);
+        assert_eq!(scene.colors.len(), 3 * 3);
+    }
+
+    #[test]
+    fn test_feature_edges() {
+        let cube = std::fs::read("tests/files/cube.stl").unwrap();
+        let mesh = crate::stl::get_data(&cube, 0.0).unwrap();
+
+        // Twelve sides and a diagonal across every face.
+        assert_eq!(mesh.edges().len(), 18 * 2);
+        assert_eq!(mesh.feature_edges(30.0_f32.to_radians()).len(), 12 * 2);
+        assert_eq!(mesh.feature_edges(100.0_f32.to_radians()).len(), 0);
+
+        // All edges of a lone triangle are on the boundary.
+        let vertices = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let triangle = Mesh::from_triangles(&vertices, &[0.0, 0.0, 1.0], 0.0);
+        assert_eq!(triangle.feature_edges(PI), vec![0, 1, 1, 2, 2, 0]);
+    }
+
+    #[test]
+    fn test_append_offsets_indices() {
+        let vertices = [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let normals = [0.0, 0.0, 1.0];
+        let mut mesh = Mesh::from_triangles(&vertices, &normals, 0.0);
+
+        mesh.append(&mesh.clone());
+
+        assert_eq!(mesh.indices, vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(mesh.normals.len(), 2 * AXES);
+    }
+}
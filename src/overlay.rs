@@ -0,0 +1,273 @@
+//! Geometry of the helpers drawn with the model: a ground grid, an axis
+//! gizmo and the bounding box with its dimensions.
+
+use crate::linalg::{transform, BoundingBox, Quaternion, Sphere, Vec3};
+
+/// Rough number of grid cells across the model.
+pub const GRID_DIVISIONS: f32 = 10.0;
+/// Size of the grid relative to the footprint of the model.
+pub const GRID_MARGIN: f32 = 1.5;
+/// Half the width of the space the axis gizmo is fitted into, its axes are of
+/// unit length.
+pub const GIZMO_EXTENT: f32 = 1.4;
+
+/// Text shown at a point of the scene.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub position: Vec3<f32>,
+    pub text: String,
+}
+
+/// Square grid on the plane the model stands on, `z` being up. The spacing
+/// is a round number of model units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    pub center: Vec3<f32>,
+    pub spacing: f32,
+    /// Number of cells from the centre to each side.
+    pub cells: u32,
+}
+
+impl Grid {
+    /// Grid under the model, centred below it and covering its footprint.
+    pub fn new(bounds: &BoundingBox) -> Self {
+        let [width, depth, height]: [f32; 3] = bounds.extent().into();
+        let footprint = width.max(depth);
+        let spacing = grid_spacing(footprint.max(height));
+        let [x, y, _]: [f32; 3] = bounds.center().into();
+        let [_, _, ground]: [f32; 3] = bounds.min.into();
+        Self {
+            center: Vec3::from([x, y, ground]),
+            spacing,
+            cells: (footprint * GRID_MARGIN * 0.5 / spacing).ceil().max(1.0) as u32,
+        }
+    }
+
+    pub fn half_size(&self) -> f32 {
+        self.cells as f32 * self.spacing
+    }
+
+    /// Pairs of `x, y, z` positions, one pair per grid line.
+    pub fn lines(&self) -> Vec<f32> {
+        let half_size = self.half_size();
+        let [x, y, z]: [f32; 3] = self.center.into();
+        let mut lines = vec![];
+        for step in 0..=2 * self.cells {
+            let offset = step as f32 * self.spacing - half_size;
+            lines.extend([x + offset, y - half_size, z, x + offset, y + half_size, z]);
+            lines.extend([x - half_size, y + offset, z, x + half_size, y + offset, z]);
+        }
+        lines
+    }
+
+    pub fn bounding_sphere(&self) -> Sphere {
+        Sphere {
+            center: self.center,
+            radius: self.half_size() * 2.0_f32.sqrt(),
+        }
+    }
+
+    /// The spacing, shown at a corner of the grid.
+    pub fn label(&self) -> Label {
+        let half_size = self.half_size();
+        Label {
+            position: self.center + Vec3::from([half_size, -half_size, 0.0]),
+            text: format_length(self.spacing),
+        }
+    }
+}
+
+/// Round step of 1, 2 or 5 times a power of ten that divides `length` into
+/// about [`GRID_DIVISIONS`] cells.
+pub fn grid_spacing(length: f32) -> f32 {
+    let rough = length / GRID_DIVISIONS;
+    if !rough.is_normal() {
+        return 1.0;
+    }
+    let magnitude = 10.0_f32.powf(rough.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .find(|step| *step >= rough * (1.0 - 1e-6))
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// Pairs of `x, y, z` positions for the twelve edges of the box.
+pub fn box_lines(bounds: &BoundingBox) -> Vec<f32> {
+    let (min, max): ([f32; 3], [f32; 3]) = (bounds.min.into(), bounds.max.into());
+    let corner = |index: usize| -> [f32; 3] {
+        [0, 1, 2].map(|axis| {
+            if index & (1 << axis) == 0 {
+                min[axis]
+            } else {
+                max[axis]
+            }
+        })
+    };
+    let mut lines = vec![];
+    for index in 0..8 {
+        for axis in 0..3 {
+            // Every edge once, from the corner at its lower end.
+            if index & (1 << axis) == 0 {
+                lines.extend(corner(index));
+                lines.extend(corner(index | (1 << axis)));
+            }
+        }
+    }
+    lines
+}
+
+/// Length of the box along each axis, shown at the middle of the edges
+/// through its minimum corner.
+pub fn dimension_labels(bounds: &BoundingBox) -> Vec<Label> {
+    let extent: [f32; 3] = bounds.extent().into();
+    (0..3)
+        .map(|axis| {
+            let mut position: [f32; 3] = bounds.min.into();
+            position[axis] += extent[axis] * 0.5;
+            Label {
+                position: Vec3::from(position),
+                text: format_length(extent[axis]),
+            }
+        })
+        .collect()
+}
+
+/// Pairs of `x, y, z` positions of the unit `x`, `y` and `z` axes, one axis
+/// after the other.
+pub fn axis_lines() -> Vec<f32> {
+    let mut lines = vec![];
+    for axis in 0..3 {
+        let mut tip = [0.0; 3];
+        tip[axis] = 1.0;
+        lines.extend([0.0; 3]);
+        lines.extend(tip);
+    }
+    lines
+}
+
+/// Names of the axes, just beyond their tips.
+pub fn axis_labels() -> Vec<Label> {
+    ["X", "Y", "Z"]
+        .into_iter()
+        .enumerate()
+        .map(|(axis, text)| {
+            let mut position = [0.0; 3];
+            position[axis] = 1.2;
+            Label {
+                position: Vec3::from(position),
+                text: text.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Column-major projection and view matrix of the axis gizmo, which turns
+/// with the model but does not move or zoom.
+pub fn gizmo_matrices(rotation: Quaternion) -> ([f32; 16], [f32; 16]) {
+    let scale = 1.0 / GIZMO_EXTENT;
+    let projection = [
+        scale, 0.0, 0.0, 0.0, //
+        0.0, scale, 0.0, 0.0, //
+        0.0, 0.0, -scale, 0.0, //
+        0.0, 0.0, 0.0, 1.0,
+    ];
+    (projection, rotation.to_matrix())
+}
+
+/// Normalized device coordinates of a point, `None` if it is behind the
+/// camera.
+pub fn project(projection: &[f32; 16], view: &[f32; 16], point: Vec3<f32>) -> Option<[f32; 2]> {
+    let [x, y, z]: [f32; 3] = point.into();
+    let [x, y, _, w] = transform(projection, transform(view, [x, y, z, 1.0]));
+    if w <= 0.0 {
+        return None;
+    }
+    Some([x / w, y / w])
+}
+
+/// Shortest decimal form with at most three decimals.
+pub fn format_length(length: f32) -> String {
+    let text = format!("{:.3}", length);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".to_string(),
+        _ => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::view::Camera;
+
+    fn bounds() -> BoundingBox {
+        BoundingBox {
+            min: Vec3::from([-10.0, 0.0, 5.0]),
+            max: Vec3::from([30.0, 20.0, 12.5]),
+        }
+    }
+
+    #[test]
+    fn test_grid_spacing() {
+        for (length, spacing) in [(40.0, 5.0), (100.0, 10.0), (0.15, 0.02), (1800.0, 200.0)] {
+            assert!((grid_spacing(length) - spacing).abs() < spacing * 1e-5);
+        }
+        assert_eq!(grid_spacing(0.0), 1.0);
+    }
+
+    #[test]
+    fn test_grid() {
+        let grid = Grid::new(&bounds());
+        assert_eq!(grid.center, Vec3::from([10.0, 10.0, 5.0]));
+        assert_eq!(grid.spacing, 5.0);
+        // 40 wide with a margin of half on each side is 60, or 6 cells of 5.
+        assert_eq!(grid.cells, 6);
+        assert_eq!(grid.lines().len(), 13 * 2 * 6);
+        assert!(grid.lines().chunks(3).all(|point| point[2] == 5.0));
+        assert_eq!(grid.label().text, "5");
+    }
+
+    #[test]
+    fn test_box_overlay() {
+        let lines = box_lines(&bounds());
+        assert_eq!(lines.len(), 12 * 2 * 3);
+        // Every edge runs along a single axis.
+        for edge in lines.chunks(6) {
+            let changed = (0..3).filter(|&axis| edge[axis] != edge[axis + 3]);
+            assert_eq!(changed.count(), 1);
+        }
+
+        let labels = dimension_labels(&bounds());
+        let texts: Vec<&str> = labels.iter().map(|label| label.text.as_str()).collect();
+        assert_eq!(texts, ["40", "20", "7.5"]);
+        assert_eq!(labels[0].position, Vec3::from([10.0, 0.0, 5.0]));
+    }
+
+    #[test]
+    fn test_project() {
+        let scene = bounds().bounding_sphere();
+        let camera = Camera::fit(&scene);
+        let projection = camera.projection_matrix(1.0, &scene);
+        let view = camera.view_matrix();
+        let [x, y] = project(&projection, &view, scene.center).unwrap();
+        assert!(x.abs() < 1e-5 && y.abs() < 1e-5);
+        let behind = camera.eye() + Vec3::from([0.0, 0.0, 1.0]);
+        assert_eq!(project(&projection, &view, behind), None);
+
+        // The gizmo of the top view shows `x` to the right and `y` up.
+        let (projection, view) = gizmo_matrices(Quaternion::IDENTITY);
+        let x_tip = project(&projection, &view, Vec3::from([1.0, 0.0, 0.0])).unwrap();
+        let y_tip = project(&projection, &view, Vec3::from([0.0, 1.0, 0.0])).unwrap();
+        assert_eq!(x_tip, [1.0 / GIZMO_EXTENT, 0.0]);
+        assert_eq!(y_tip, [0.0, 1.0 / GIZMO_EXTENT]);
+    }
+
+    #[test]
+    fn test_format_length() {
+        assert_eq!(format_length(120.0), "120");
+        assert_eq!(format_length(0.25), "0.25");
+        assert_eq!(format_length(1.23456), "1.235");
+        assert_eq!(format_length(-0.0001), "0");
+    }
+}
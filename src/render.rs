@@ -0,0 +1,434 @@
+//! Software rendering of the solid surface of a mesh, for thumbnails where
+//! there is no GPU. It follows the WebGL viewer: the same camera, projection
+//! and Blinn-Phong shading with a light attached to the camera.
+
+use std::f32::consts::PI;
+
+use crate::linalg::{transform, Vec3, VecOps};
+use crate::mesh::{Mesh, ShadedMesh, DEFAULT_COLOR};
+use crate::view::ViewerState;
+
+pub const BACKGROUND_COLOR: [f32; 4] = [0.375, 0.375, 0.375, 1.0];
+/// Unit direction towards the light in view space.
+pub const LIGHT_DIRECTION: [f32; 3] = [0.30, 0.50, 0.81];
+pub const AMBIENT: f32 = 0.25;
+pub const DIFFUSE: f32 = 0.65;
+pub const SPECULAR: f32 = 0.35;
+pub const SHININESS: f32 = 32.0;
+/// Facets meeting at a larger angle are not smoothed into each other.
+pub const CREASE_ANGLE: f32 = 30.0 * PI / 180.0;
+/// Samples per pixel along each axis, averaged to smooth the edges.
+pub const SUPERSAMPLING: u32 = 2;
+
+const AXES: usize = 3;
+const CHANNELS: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderError {
+    /// The buffers for an image of this size cannot be allocated.
+    TooLarge { width: u32, height: u32 },
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooLarge { width, height } => write!(
+                f,
+                "not enough memory to render an image of {} by {} pixels",
+                width, height
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+/// RGBA pixels with 8 bits per channel, top row first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let idx = (y as usize * self.width as usize + x as usize) * CHANNELS;
+        self.pixels[idx..idx + CHANNELS].try_into().unwrap()
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let mut bytes = vec![];
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+        Ok(bytes)
+    }
+}
+
+/// Colour of a surface point given in view space, lit from
+/// [`LIGHT_DIRECTION`]. The normal is flipped on back faces by the caller, as
+/// STL files are not required to wind their facets consistently.
+pub fn shade(color: [f32; 3], normal: Vec3<f32>, position: Vec3<f32>) -> [f32; 3] {
+    let light = Vec3::from(LIGHT_DIRECTION);
+    let normal = normal.normalize();
+    let view = position.scale(-1.0).normalize();
+    let halfway = (light + view).normalize();
+
+    let lambert = normal.dot(&light).max(0.0);
+    let highlight = if lambert > 0.0 {
+        normal.dot(&halfway).max(0.0).powf(SHININESS)
+    } else {
+        0.0
+    };
+    color.map(|c| c * (AMBIENT + DIFFUSE * lambert) + SPECULAR * highlight)
+}
+
+/// Draws the mesh as the viewer with `state` would on a canvas of `width` by
+/// `height` pixels, over `background`, which may be transparent. The buffers
+/// take about 80 bytes per pixel, failing to allocate them is an error.
+pub fn render(
+    mesh: &Mesh,
+    state: &ViewerState,
+    width: u32,
+    height: u32,
+    background: [f32; 4],
+) -> Result<Image, RenderError> {
+    let too_large = RenderError::TooLarge { width, height };
+    let shaded = if state.display.smooth_shading {
+        mesh.smooth_shaded(CREASE_ANGLE)
+    } else {
+        mesh.flat_shaded()
+    };
+    let view = state.camera.view_matrix();
+    let projection = state
+        .camera
+        .projection_matrix(width as f32 / height as f32, &state.scene);
+
+    let (Some(sample_width), Some(sample_height)) = (
+        width.checked_mul(SUPERSAMPLING),
+        height.checked_mul(SUPERSAMPLING),
+    ) else {
+        return Err(too_large);
+    };
+    let mut target =
+        Target::new(sample_width, sample_height, background).ok_or(too_large.clone())?;
+    let vertices: Vec<Vertex> = (0..shaded.positions.len() / AXES)
+        .map(|idx| Vertex::new(&shaded, idx, &view, &projection))
+        .collect();
+    for triangle in shaded.indices.chunks_exact(3) {
+        let triangle = [0, 1, 2].map(|corner| vertices[triangle[corner] as usize]);
+        for triangle in clip_near(triangle) {
+            target.draw(triangle);
+        }
+    }
+    target.resolve(SUPERSAMPLING).ok_or(too_large)
+}
+
+/// Number of interpolated values per vertex: the position and normal in view
+/// space and the colour.
+const VARYINGS: usize = 9;
+
+#[derive(Debug, Clone, Copy)]
+struct Vertex {
+    clip: [f32; 4],
+    varyings: [f32; VARYINGS],
+}
+
+impl Vertex {
+    fn new(mesh: &ShadedMesh, idx: usize, view: &[f32; 16], projection: &[f32; 16]) -> Self {
+        let range = idx * AXES..(idx + 1) * AXES;
+        let [x, y, z]: [f32; 3] = mesh.positions[range.clone()].try_into().unwrap();
+        let [nx, ny, nz]: [f32; 3] = mesh.normals[range.clone()].try_into().unwrap();
+        let [r, g, b] = mesh
+            .colors
+            .get(range)
+            .map_or(DEFAULT_COLOR, |color| color.try_into().unwrap());
+
+        let position = transform(view, [x, y, z, 1.0]);
+        // The view matrix only rotates and translates.
+        let [nx, ny, nz, _] = transform(view, [nx, ny, nz, 0.0]);
+        Self {
+            clip: transform(projection, position),
+            varyings: [position[0], position[1], position[2], nx, ny, nz, r, g, b],
+        }
+    }
+
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let mut result = *self;
+        for (value, other) in result.clip.iter_mut().zip(other.clip) {
+            *value += (other - *value) * t;
+        }
+        for (value, other) in result.varyings.iter_mut().zip(other.varyings) {
+            *value += (other - *value) * t;
+        }
+        result
+    }
+
+    /// Signed distance to the near plane in clip space, negative in front
+    /// of it.
+    fn near_distance(&self) -> f32 {
+        self.clip[2] + self.clip[3]
+    }
+}
+
+/// Cuts off the part of the triangle in front of the near plane, which
+/// leaves up to two triangles.
+fn clip_near(triangle: [Vertex; 3]) -> Vec<[Vertex; 3]> {
+    let mut polygon = vec![];
+    for (idx, current) in triangle.iter().enumerate() {
+        let next = &triangle[(idx + 1) % 3];
+        let (d_current, d_next) = (current.near_distance(), next.near_distance());
+        if d_current >= 0.0 {
+            polygon.push(*current);
+        }
+        if (d_current >= 0.0) != (d_next >= 0.0) {
+            polygon.push(current.lerp(next, d_current / (d_current - d_next)));
+        }
+    }
+    (1..polygon.len().saturating_sub(1))
+        .map(|idx| [polygon[0], polygon[idx], polygon[idx + 1]])
+        .collect()
+}
+
+/// Colour and depth buffer of the samples, colours premultiplied by alpha.
+struct Target {
+    width: u32,
+    height: u32,
+    colors: Vec<[f32; 4]>,
+    depths: Vec<f32>,
+}
+
+impl Target {
+    /// `None` if the buffers cannot be allocated.
+    fn new(width: u32, height: u32, [r, g, b, a]: [f32; 4]) -> Option<Self> {
+        let samples = (width as usize).checked_mul(height as usize)?;
+        let mut colors = vec![];
+        let mut depths = vec![];
+        colors.try_reserve_exact(samples).ok()?;
+        depths.try_reserve_exact(samples).ok()?;
+        colors.resize(samples, [r * a, g * a, b * a, a]);
+        depths.resize(samples, f32::INFINITY);
+        Some(Self {
+            width,
+            height,
+            colors,
+            depths,
+        })
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+
+    /// Fills the samples whose centres the triangle covers, interpolating
+    /// the varyings perspective-correctly.
+    fn draw(&mut self, triangle: [Vertex; 3]) {
+        let (width, height) = (self.width as f32, self.height as f32);
+        // Window coordinates with `y` down, depth and the inverse of `w`.
+        let screen = triangle.map(|vertex| {
+            let [x, y, z, w] = vertex.clip;
+            [
+                (x / w + 1.0) * 0.5 * width,
+                (1.0 - y / w) * 0.5 * height,
+                z / w,
+                1.0 / w,
+            ]
+        });
+        let area = edge(screen[0], screen[1], screen[2]);
+        if area == 0.0 || !area.is_finite() {
+            return;
+        }
+        // Counter-clockwise on screen is clockwise with `y` down.
+        let front_facing = area < 0.0;
+
+        let min = |axis: usize| screen.iter().map(|p| p[axis]).fold(f32::INFINITY, f32::min);
+        let max = |axis: usize| {
+            screen
+                .iter()
+                .map(|p| p[axis])
+                .fold(f32::NEG_INFINITY, f32::max)
+        };
+        let x_range = (min(0).floor().max(0.0) as u32)..(max(0).ceil().min(width) as u32);
+        let y_range = (min(1).floor().max(0.0) as u32)..(max(1).ceil().min(height) as u32);
+
+        for y in y_range {
+            for x in x_range.clone() {
+                let sample = [x as f32 + 0.5, y as f32 + 0.5, 0.0, 0.0];
+                let weights = [
+                    edge(screen[1], screen[2], sample) / area,
+                    edge(screen[2], screen[0], sample) / area,
+                    edge(screen[0], screen[1], sample) / area,
+                ];
+                if weights.iter().any(|&weight| weight < 0.0) {
+                    continue;
+                }
+                let depth: f32 = (0..3).map(|idx| weights[idx] * screen[idx][2]).sum();
+                let idx = self.index(x, y);
+                if depth < -1.0 || depth >= self.depths[idx] {
+                    continue;
+                }
+                self.depths[idx] = depth;
+
+                let perspective = [0, 1, 2].map(|idx| weights[idx] * screen[idx][3]);
+                let total: f32 = perspective.iter().sum();
+                let mut varyings = [0.0; VARYINGS];
+                for (corner, vertex) in triangle.iter().enumerate() {
+                    for (value, corner_value) in varyings.iter_mut().zip(vertex.varyings) {
+                        *value += corner_value * perspective[corner] / total;
+                    }
+                }
+                let position = Vec3::from([varyings[0], varyings[1], varyings[2]]);
+                let mut normal = Vec3::from([varyings[3], varyings[4], varyings[5]]);
+                if !front_facing {
+                    normal = normal.scale(-1.0);
+                }
+                let [r, g, b] = shade([varyings[6], varyings[7], varyings[8]], normal, position);
+                self.colors[idx] = [r, g, b, 1.0];
+            }
+        }
+    }
+
+    /// Averages blocks of `factor` by `factor` samples into pixels.
+    fn resolve(&self, factor: u32) -> Option<Image> {
+        let (width, height) = (self.width / factor, self.height / factor);
+        let mut pixels = vec![];
+        pixels
+            .try_reserve_exact(width as usize * height as usize * CHANNELS)
+            .ok()?;
+        for y in 0..height {
+            for x in 0..width {
+                let mut sum = [0.0; 4];
+                for sample_y in y * factor..(y + 1) * factor {
+                    for sample_x in x * factor..(x + 1) * factor {
+                        let color = self.colors[self.index(sample_x, sample_y)];
+                        for (sum, value) in sum.iter_mut().zip(color) {
+                            *sum += value;
+                        }
+                    }
+                }
+                let alpha = sum[3];
+                let rgb = [0, 1, 2].map(|channel| {
+                    if alpha > 0.0 {
+                        sum[channel] / alpha
+                    } else {
+                        0.0
+                    }
+                });
+                let coverage = alpha / (factor * factor) as f32;
+                pixels.extend(rgb.into_iter().chain([coverage]).map(to_byte));
+            }
+        }
+        Some(Image {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p` on screen.
+fn edge(a: [f32; 4], b: [f32; 4], p: [f32; 4]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::keymap::Action;
+    use crate::view::StandardView;
+
+    fn cube_view(view: StandardView) -> (Mesh, ViewerState) {
+        let cube = std::fs::read("tests/files/cube.stl").unwrap();
+        let mesh = crate::stl::get_data(&cube, 0.0).unwrap();
+        let mut state = ViewerState {
+            transition_duration: 0.0,
+            ..ViewerState::default()
+        };
+        state.set_scene(mesh.bounds().unwrap().bounding_sphere());
+        state.apply(Action::View(view));
+        (mesh, state)
+    }
+
+    #[test]
+    fn test_render_cube() {
+        let (mesh, state) = cube_view(StandardView::Front);
+        let image = render(&mesh, &state, 64, 48, BACKGROUND_COLOR).unwrap();
+        assert_eq!(image.pixels.len(), 64 * 48 * 4);
+
+        let background = BACKGROUND_COLOR.map(to_byte);
+        assert_eq!(image.pixel(0, 0), background);
+        // Looking straight at the front face, which is lit like the shader
+        // would light a face towards the camera.
+        let front = shade(
+            DEFAULT_COLOR,
+            Vec3::from([0.0, 0.0, 1.0]),
+            Vec3::from([0.0, 0.0, -1.0]),
+        );
+        let [r, g, b, a] = image.pixel(32, 24);
+        assert_eq!([r, g, b], front.map(to_byte));
+        assert_eq!(a, 255);
+
+        let transparent = render(&mesh, &state, 64, 48, [0.0; 4]).unwrap();
+        assert_eq!(transparent.pixel(0, 0), [0; 4]);
+        assert_eq!(transparent.pixel(32, 24), image.pixel(32, 24));
+    }
+
+    #[test]
+    fn test_too_large() {
+        let (mesh, state) = cube_view(StandardView::Iso);
+        assert_eq!(
+            render(&mesh, &state, u32::MAX, 1, BACKGROUND_COLOR),
+            Err(RenderError::TooLarge {
+                width: u32::MAX,
+                height: 1
+            })
+        );
+        assert!(render(&mesh, &state, 1 << 30, 1 << 30, BACKGROUND_COLOR).is_err());
+    }
+
+    #[test]
+    fn test_clip_near() {
+        let vertex = |z: f32| Vertex {
+            clip: [0.0, 0.0, z, 1.0],
+            varyings: [z; VARYINGS],
+        };
+        assert_eq!(clip_near([vertex(0.0), vertex(0.5), vertex(1.0)]).len(), 1);
+        assert_eq!(
+            clip_near([vertex(-2.0), vertex(-3.0), vertex(-4.0)]).len(),
+            0
+        );
+        assert_eq!(
+            clip_near([vertex(-2.0), vertex(-3.0), vertex(1.0)]).len(),
+            1
+        );
+        // Cutting off a corner leaves a quadrilateral.
+        let clipped = clip_near([vertex(-2.0), vertex(1.0), vertex(1.0)]);
+        assert_eq!(clipped.len(), 2);
+        assert!(clipped
+            .iter()
+            .flatten()
+            .all(|vertex| vertex.near_distance() >= -1e-6));
+    }
+
+    #[test]
+    fn test_png() {
+        let (mesh, state) = cube_view(StandardView::Iso);
+        let image = render(&mesh, &state, 16, 16, BACKGROUND_COLOR).unwrap();
+
+        let png = image.to_png().unwrap();
+        let decoder = png::Decoder::new(png.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!((info.width, info.height), (16, 16));
+        assert_eq!(pixels, image.pixels);
+    }
+}
@@ -1,6 +1,4 @@
-use std::slice::Iter;
-
-use crate::linalg::{Vec3, VecOps};
+use crate::mesh::{Mesh, DEFAULT_COLOR};
 
 const STL_HEADER_BYTES: usize = 0x50;
 const STL_NUMBER_FACETS_BYTES: usize = 0x4;
@@ -9,118 +7,522 @@ const STL_F32_BYTES: usize = 4;
 const STL_EXTRA_BYTES: usize = 2;
 const STL_AXES: usize = 3;
 const STL_VERTICES_PER_FACET: usize = 3;
+const STL_ASCII_SOLID: &str = "solid";
+const UTF8_BOM: &[u8] = b"\xef\xbb\xbf";
+/// Header keys of Materialise Magics, followed by the RGBA colour of the
+/// whole object and, for the material, its diffuse colour.
+const STL_MAGICS_COLOR: &[u8] = b"COLOR=";
+const STL_MAGICS_MATERIAL: &[u8] = b"MATERIAL=";
+/// Magics keys with the length of their value, one RGBA colour for the
+/// object and three for the material.
+const STL_MAGICS_KEYS: [(&[u8], usize); 2] = [(STL_MAGICS_COLOR, 4), (STL_MAGICS_MATERIAL, 12)];
+/// Bit of the attribute bytes telling whether a facet has a colour.
+const STL_COLOR_FLAG: u16 = 0x8000;
+const STL_COLOR_BITS: u32 = 5;
+const STL_COLOR_MASK: u16 = 0x1f;
 
-#[derive(Debug, Clone)]
-pub struct InvalidFileContentError {
-    msg: String,
+#[derive(Debug, Clone, PartialEq)]
+pub enum StlError {
+    /// The file ends before the header and the facet count are complete.
+    TruncatedHeader { len: usize },
+    /// The header declares a different number of facets than the payload holds.
+    FacetCountMismatch { expected: u32, actual: usize },
+    /// The payload ends with a partial facet record starting at `offset`.
+    MisalignedPayload { offset: usize, trailing: usize },
+    /// A coordinate at `offset` is NaN or infinite.
+    NonFiniteCoordinate { offset: usize, value: f32 },
+    /// An ASCII file deviates from the `solid ... endsolid` grammar.
+    AsciiSyntax {
+        line: usize,
+        column: usize,
+        offset: usize,
+        expected: &'static str,
+        found: Option<String>,
+    },
 }
 
-impl InvalidFileContentError {
-    fn new(msg: &str) -> Self {
-        Self { msg: msg.into() }
+impl std::fmt::Display for StlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TruncatedHeader { len } => write!(
+                f,
+                "STL: file is {} bytes long but the header needs {}",
+                len,
+                STL_HEADER_BYTES + STL_NUMBER_FACETS_BYTES
+            ),
+            Self::FacetCountMismatch { expected, actual } => write!(
+                f,
+                "STL: header declares {} facets but the payload holds {}",
+                expected, actual
+            ),
+            Self::MisalignedPayload { offset, trailing } => write!(
+                f,
+                "STL: payload is not aligned properly, {} trailing bytes at offset {}",
+                trailing, offset
+            ),
+            Self::NonFiniteCoordinate { offset, value } => {
+                write!(
+                    f,
+                    "STL: coordinate {} at offset {} is not finite",
+                    value, offset
+                )
+            }
+            Self::AsciiSyntax {
+                line,
+                column,
+                expected,
+                found,
+                ..
+            } => match found {
+                Some(found) => write!(
+                    f,
+                    "STL: expected {} but found `{}` at line {}, column {}",
+                    expected, found, line, column
+                ),
+                None => write!(
+                    f,
+                    "STL: expected {} but found end of file at line {}, column {}",
+                    expected, line, column
+                ),
+            },
+        }
     }
 }
 
-impl std::fmt::Display for InvalidFileContentError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.msg)
+impl std::error::Error for StlError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StlFormat {
+    Ascii,
+    Binary,
+}
+
+/// Facets of an STL file exactly as stored, before welding or normalization.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StlFile {
+    pub format: StlFormat,
+    /// The 80 byte header of a binary file or the name of an ASCII solid.
+    pub header: String,
+    /// Flat `x, y, z` normal per facet, possibly zero or wrong.
+    pub normals: Vec<f32>,
+    /// Flat `x, y, z` coordinates, three vertices per facet.
+    pub vertices: Vec<f32>,
+    /// RGB colour per facet of a binary file storing colours in its
+    /// attribute bytes, `None` for facets using the default material. Empty
+    /// if no facet has a colour.
+    pub colors: Vec<Option<[u8; 3]>>,
+}
+
+impl StlFile {
+    pub fn num_facets(&self) -> usize {
+        self.normals.len() / STL_AXES
+    }
+
+    /// Welds vertices closer than `weld_tolerance` into an indexed mesh.
+    pub fn to_mesh(&self, weld_tolerance: f32) -> Mesh {
+        let mut mesh = Mesh::from_triangles(&self.vertices, &self.normals, weld_tolerance);
+        mesh.colors = self
+            .colors
+            .iter()
+            .flat_map(|color| color.map_or(DEFAULT_COLOR, |color| color.map(|c| c as f32 / 255.0)))
+            .collect();
+        mesh
     }
 }
 
-pub fn get_data(bytes: &[u8]) -> Result<(Vec<f32>, u32), InvalidFileContentError> {
+/// Parses an ASCII or binary STL file into an indexed mesh, welding vertices
+/// closer than `weld_tolerance` in the units of the file. Coordinates are kept
+/// as stored, see [`Mesh::bounds`] for their extent.
+pub fn get_data(bytes: &[u8], weld_tolerance: f32) -> Result<Mesh, StlError> {
+    Ok(parse(bytes)?.to_mesh(weld_tolerance))
+}
+
+/// Parses an ASCII or binary STL file without altering its facets.
+pub fn parse(bytes: &[u8]) -> Result<StlFile, StlError> {
+    if is_ascii(bytes) {
+        get_ascii_vertices(bytes)
+    } else {
+        parse_binary(bytes)
+    }
+}
+
+fn parse_binary(bytes: &[u8]) -> Result<StlFile, StlError> {
     let (payload, num_facets) = extract_data(bytes)?;
-    let vertices = get_vertices(payload, num_facets)?;
-    let num_vertices = STL_AXES as u32 * num_facets;
+    let (normals, vertices, attributes) = get_vertices(payload, num_facets)?;
+    Ok(StlFile {
+        format: StlFormat::Binary,
+        header: header_text(&bytes[..STL_HEADER_BYTES]),
+        normals,
+        vertices,
+        colors: facet_colors(&bytes[..STL_HEADER_BYTES], &attributes),
+    })
+}
+
+/// Encodes the facets as a binary STL file, with colours in the VisCAM and
+/// SolidView convention. Magics keys are dropped from the header, as they
+/// would switch readers to the Magics convention.
+pub fn write_binary(file: &StlFile) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(
+        STL_HEADER_BYTES + STL_NUMBER_FACETS_BYTES + file.num_facets() * STL_FACET_RECORD_BYTES,
+    );
+    let mut header = without_magics_keys(file.header.as_bytes());
+    header.resize(STL_HEADER_BYTES, b' ');
+    bytes.extend_from_slice(&header);
+    bytes.extend_from_slice(&(file.num_facets() as u32).to_le_bytes());
 
-    Ok((vertices, num_vertices))
+    for (idx, (normal, facet)) in file
+        .normals
+        .chunks_exact(STL_AXES)
+        .zip(
+            file.vertices
+                .chunks_exact(STL_VERTICES_PER_FACET * STL_AXES),
+        )
+        .enumerate()
+    {
+        for value in normal.iter().chain(facet) {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        let attribute = match file.colors.get(idx).copied().flatten() {
+            Some([r, g, b]) => STL_COLOR_FLAG | pack_color([b, g, r]),
+            None => 0,
+        };
+        bytes.extend_from_slice(&attribute.to_le_bytes());
+    }
+
+    bytes
 }
 
-fn get_vertices(payload: Vec<u8>, num_facets: u32) -> Result<Vec<f32>, InvalidFileContentError> {
-    let mut payload = payload.iter();
-    let mut min_values = Vec3::new(f32::INFINITY);
-    let mut max_values = Vec3::new(f32::NEG_INFINITY);
-    let mut vertices: Vec<f32> = vec![];
+/// Encodes the facets as an ASCII STL file.
+pub fn write_ascii(file: &StlFile) -> String {
+    let mut text = format!("{} {}\n", STL_ASCII_SOLID, file.header);
 
-    for _ in 0..num_facets {
-        payload.by_ref().take(STL_AXES * STL_F32_BYTES).count();
-        for _ in 0..STL_VERTICES_PER_FACET {
-            for idx in 0..STL_AXES {
-                get_vertex(
-                    payload.by_ref(),
-                    max_values.get_mut(idx).unwrap(),
-                    min_values.get_mut(idx).unwrap(),
-                    &mut vertices,
-                );
-            }
+    for (normal, facet) in file.normals.chunks_exact(STL_AXES).zip(
+        file.vertices
+            .chunks_exact(STL_VERTICES_PER_FACET * STL_AXES),
+    ) {
+        text += &format!(
+            "  facet normal {:e} {:e} {:e}\n    outer loop\n",
+            normal[0], normal[1], normal[2]
+        );
+        for v in facet.chunks_exact(STL_AXES) {
+            text += &format!("      vertex {:e} {:e} {:e}\n", v[0], v[1], v[2]);
         }
-        payload.by_ref().take(STL_EXTRA_BYTES).count();
+        text += "    endloop\n  endfacet\n";
     }
 
-    if payload.next().is_some() {
-        return Err(InvalidFileContentError::new("STL: Payload is too large"));
+    text + &format!("end{} {}\n", STL_ASCII_SOLID, file.header)
+}
+
+/// Removes the Magics keys and the colours following them.
+fn without_magics_keys(header: &[u8]) -> Vec<u8> {
+    let mut header = header.to_vec();
+    for (key, value_len) in STL_MAGICS_KEYS {
+        while let Some(start) = header.windows(key.len()).position(|window| window == key) {
+            let end = (start + key.len() + value_len).min(header.len());
+            header.drain(start..end);
+        }
+    }
+    header
+}
+
+/// Binary headers are free-form, so anything but printable ASCII is dropped.
+fn header_text(header: &[u8]) -> String {
+    header
+        .iter()
+        .map(|&b| if b.is_ascii_graphic() { b as char } else { ' ' })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Binary files are free to start their header with `solid` as well, so a
+/// payload whose size matches the declared number of facets is always
+/// treated as binary. So are files with a NUL byte in the first line, which
+/// names the solid, or control characters after it, which text does not have
+/// but a damaged binary file almost certainly does.
+fn is_ascii(bytes: &[u8]) -> bool {
+    let prefix = STL_HEADER_BYTES + STL_NUMBER_FACETS_BYTES;
+    if bytes.len() >= prefix {
+        let num_facets: [u8; STL_NUMBER_FACETS_BYTES] =
+            bytes[STL_HEADER_BYTES..prefix].try_into().unwrap();
+        let num_facets = u32::from_le_bytes(num_facets) as usize;
+        if num_facets.checked_mul(STL_FACET_RECORD_BYTES) == Some(bytes.len() - prefix) {
+            return false;
+        }
+    }
+    let bytes = bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes);
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let (first_line, rest) = match bytes[start..].iter().position(|&b| b == b'\n') {
+        Some(end) => bytes[start..].split_at(end),
+        None => (&bytes[start..], &[][..]),
+    };
+    first_line.starts_with(STL_ASCII_SOLID.as_bytes())
+        && !first_line.contains(&0)
+        && !rest
+            .iter()
+            .any(|b| b.is_ascii_control() && !b.is_ascii_whitespace())
+}
+
+/// Colour of every facet, empty if there are none. Magics marks its files with
+/// `COLOR=` or `MATERIAL=` in the header, stores red in the low bits and
+/// clears the flag for facets with a colour of their own, the others take the
+/// colour of the object. VisCAM and SolidView store blue in the low bits and
+/// set the flag for facets with a colour.
+fn facet_colors(header: &[u8], attributes: &[u16]) -> Vec<Option<[u8; 3]>> {
+    let magics = STL_MAGICS_KEYS
+        .iter()
+        .find_map(|(key, _)| header_value(header, key));
+    let colors: Vec<Option<[u8; 3]>> = attributes
+        .iter()
+        .map(|&attribute| match magics {
+            Some(_) if attribute & STL_COLOR_FLAG == 0 => Some(unpack_color(attribute)),
+            Some(object) => object,
+            None if attribute & STL_COLOR_FLAG != 0 => {
+                let [b, g, r] = unpack_color(attribute);
+                Some([r, g, b])
+            }
+            None => None,
+        })
+        .collect();
+    if colors.iter().any(Option::is_some) {
+        colors
+    } else {
+        vec![]
     }
+}
+
+/// `Some` if the header contains `key`, holding the RGB bytes after it if
+/// there are any.
+fn header_value(header: &[u8], key: &[u8]) -> Option<Option<[u8; 3]>> {
+    let start = header.windows(key.len()).position(|window| window == key)? + key.len();
+    Some(
+        header
+            .get(start..start + 3)
+            .map(|rgb| [rgb[0], rgb[1], rgb[2]]),
+    )
+}
+
+/// Expands three 5 bit channels, the first one in the low bits, to 8 bits.
+fn unpack_color(attribute: u16) -> [u8; 3] {
+    [0, 1, 2].map(|channel| {
+        let value = ((attribute >> (channel * STL_COLOR_BITS)) & STL_COLOR_MASK) as u8;
+        (value << 3) | (value >> 2)
+    })
+}
 
-    let half_lengths = (max_values - min_values).scale(0.5);
-    let translations = half_lengths - max_values;
-    let scale = half_lengths.get_max();
+fn pack_color(color: [u8; 3]) -> u16 {
+    color
+        .iter()
+        .enumerate()
+        .map(|(channel, &value)| ((value >> 3) as u16) << (channel as u32 * STL_COLOR_BITS))
+        .sum()
+}
 
-    vertices.iter_mut().enumerate().for_each(|(idx, v)| {
-        *v += *translations.get(idx % 3).unwrap();
-    });
+/// Returns the facet normals, the vertices and the attribute bytes of every
+/// facet.
+#[allow(clippy::type_complexity)]
+fn get_vertices(
+    payload: &[u8],
+    num_facets: u32,
+) -> Result<(Vec<f32>, Vec<f32>, Vec<u16>), StlError> {
+    let mut normals: Vec<f32> = Vec::with_capacity(num_facets as usize * STL_AXES);
+    let mut vertices: Vec<f32> =
+        Vec::with_capacity(num_facets as usize * STL_VERTICES_PER_FACET * STL_AXES);
+    let mut attributes: Vec<u16> = Vec::with_capacity(num_facets as usize);
+    let payload_offset = STL_HEADER_BYTES + STL_NUMBER_FACETS_BYTES;
 
-    vertices.iter_mut().for_each(|v| {
-        *v /= scale;
-    });
+    for (idx, record) in payload.chunks_exact(STL_FACET_RECORD_BYTES).enumerate() {
+        let record_offset = payload_offset + idx * STL_FACET_RECORD_BYTES;
+        let mut values = record
+            .chunks_exact(STL_F32_BYTES)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()));
+        // Broken normals are recomputed from the winding, so they are not checked.
+        normals.extend(values.by_ref().take(STL_AXES));
+        for (coordinate, value) in values.take(STL_VERTICES_PER_FACET * STL_AXES).enumerate() {
+            if !value.is_finite() {
+                return Err(StlError::NonFiniteCoordinate {
+                    offset: record_offset + (STL_AXES + coordinate) * STL_F32_BYTES,
+                    value,
+                });
+            }
+            vertices.push(value);
+        }
+        let extra = &record[STL_FACET_RECORD_BYTES - STL_EXTRA_BYTES..];
+        attributes.push(u16::from_le_bytes(extra.try_into().unwrap()));
+    }
 
-    Ok(vertices)
+    Ok((normals, vertices, attributes))
 }
 
-fn get_vertex(
-    payload: &mut Iter<u8>,
-    max_value: &mut f32,
-    min_value: &mut f32,
-    vertices: &mut Vec<f32>,
-) {
-    let v: Vec<u8> = payload.take(STL_F32_BYTES).cloned().collect();
-    let v: [u8; STL_F32_BYTES] = v.try_into().unwrap();
-    let v = f32::from_le_bytes(v);
+fn get_ascii_vertices(bytes: &[u8]) -> Result<StlFile, StlError> {
+    let mut tokens = AsciiTokens::new(bytes);
+    let mut header: Option<String> = None;
+    let mut normals: Vec<f32> = vec![];
+    let mut vertices: Vec<f32> = vec![];
 
-    *max_value = max_value.max(v);
-    *min_value = min_value.min(v);
-    vertices.push(v);
+    // Some exporters concatenate several solids into a single file.
+    while tokens.peek().is_some() {
+        tokens.expect(STL_ASCII_SOLID)?;
+        let name = tokens.rest_of_line();
+        header.get_or_insert(name);
+        loop {
+            match tokens.next() {
+                Some((b"facet", _)) => {}
+                Some((b"endsolid", _)) => {
+                    tokens.rest_of_line();
+                    break;
+                }
+                token => return Err(tokens.error("`facet` or `endsolid`", token)),
+            }
+            tokens.expect("normal")?;
+            for _ in 0..STL_AXES {
+                normals.push(tokens.float()?.0);
+            }
+            tokens.expect("outer")?;
+            tokens.expect("loop")?;
+            for _ in 0..STL_VERTICES_PER_FACET {
+                tokens.expect("vertex")?;
+                for _ in 0..STL_AXES {
+                    vertices.push(tokens.coordinate()?);
+                }
+            }
+            tokens.expect("endloop")?;
+            tokens.expect("endfacet")?;
+        }
+    }
+
+    Ok(StlFile {
+        format: StlFormat::Ascii,
+        header: header.unwrap_or_default(),
+        normals,
+        vertices,
+        colors: vec![],
+    })
+}
+
+/// Whitespace separated tokens of an ASCII STL file together with their byte
+/// offset into the file. Keywords and numbers are ASCII, so the file is only
+/// decoded where it holds names.
+#[derive(Clone)]
+struct AsciiTokens<'a> {
+    text: &'a [u8],
+    pos: usize,
 }
 
-fn extract_data(bytes: &[u8]) -> Result<(Vec<u8>, u32), InvalidFileContentError> {
-    let mut b_it = bytes.iter();
-    let header: Vec<&u8> = b_it.by_ref().take(STL_HEADER_BYTES).collect();
-    if header.len() != STL_HEADER_BYTES {
-        return Err(InvalidFileContentError::new("STL: header too short"));
+impl<'a> AsciiTokens<'a> {
+    fn new(text: &'a [u8]) -> Self {
+        Self {
+            text,
+            pos: if text.starts_with(UTF8_BOM) {
+                UTF8_BOM.len()
+            } else {
+                0
+            },
+        }
+    }
+
+    fn next(&mut self) -> Option<(&'a [u8], usize)> {
+        let rest = &self.text[self.pos..];
+        let start = self.pos + rest.iter().position(|b| !b.is_ascii_whitespace())?;
+        let len = self.text[start..]
+            .iter()
+            .position(u8::is_ascii_whitespace)
+            .unwrap_or(self.text.len() - start);
+        self.pos = start + len;
+        Some((&self.text[start..self.pos], start))
     }
-    let num_facets: Vec<u8> = b_it
-        .by_ref()
-        .take(STL_NUMBER_FACETS_BYTES)
-        .cloned()
-        .collect();
-    if num_facets.len() != STL_NUMBER_FACETS_BYTES {
-        return Err(InvalidFileContentError::new(
-            "STL: number of facets not u32",
-        ));
+
+    fn peek(&self) -> Option<&'a [u8]> {
+        self.clone().next().map(|(token, _)| token)
     }
-    let num_facets: Result<[u8; 4], _> = num_facets.try_into();
-    if num_facets.is_err() {
-        return Err(InvalidFileContentError::new(""));
+
+    /// Consumes the remainder of the current line, e.g. the name of a solid,
+    /// replacing bytes which are not UTF-8.
+    fn rest_of_line(&mut self) -> String {
+        let rest = &self.text[self.pos..];
+        let len = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+        self.pos += len;
+        let name = String::from_utf8_lossy(&rest[..len]);
+        let words: Vec<&str> = name.split_ascii_whitespace().collect();
+        words.join(" ")
     }
-    let num_facets = u32::from_le_bytes(num_facets.unwrap());
-    let payload: Vec<_> = b_it.by_ref().cloned().collect();
-    if payload.len() % STL_FACET_RECORD_BYTES != 0 {
-        return Err(InvalidFileContentError::new(
-            "STL: payload is not aligned properly",
-        ));
+    /// Builds a syntax error for `token`, or for the end of the file if there
+    /// is none.
+    fn error(&self, expected: &'static str, token: Option<(&[u8], usize)>) -> StlError {
+        let (found, offset) = match token {
+            Some((token, offset)) => (Some(String::from_utf8_lossy(token).into_owned()), offset),
+            None => (None, self.text.len()),
+        };
+        let before = &self.text[..offset];
+        let line_offset = before
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |idx| idx + 1);
+        StlError::AsciiSyntax {
+            line: before.iter().filter(|&&b| b == b'\n').count() + 1,
+            column: String::from_utf8_lossy(&before[line_offset..])
+                .chars()
+                .count()
+                + 1,
+            offset,
+            expected,
+            found,
+        }
     }
-    if payload.len() / STL_FACET_RECORD_BYTES != (num_facets as usize) {
-        return Err(InvalidFileContentError::new(
-            "STL: payload does not match specified length",
-        ));
+
+    fn expect(&mut self, keyword: &'static str) -> Result<(), StlError> {
+        match self.next() {
+            Some((token, _)) if token == keyword.as_bytes() => Ok(()),
+            token => Err(self.error(keyword, token)),
+        }
+    }
+
+    fn float(&mut self) -> Result<(f32, usize), StlError> {
+        match self.next() {
+            Some((token, offset)) => {
+                match std::str::from_utf8(token).ok().and_then(|t| t.parse().ok()) {
+                    Some(value) => Ok((value, offset)),
+                    None => Err(self.error("a number", Some((token, offset)))),
+                }
+            }
+            None => Err(self.error("a number", None)),
+        }
+    }
+
+    fn coordinate(&mut self) -> Result<f32, StlError> {
+        let (value, offset) = self.float()?;
+        if !value.is_finite() {
+            return Err(StlError::NonFiniteCoordinate { offset, value });
+        }
+        Ok(value)
+    }
+}
+
+fn extract_data(bytes: &[u8]) -> Result<(&[u8], u32), StlError> {
+    let prefix = STL_HEADER_BYTES + STL_NUMBER_FACETS_BYTES;
+    if bytes.len() < prefix {
+        return Err(StlError::TruncatedHeader { len: bytes.len() });
+    }
+    let num_facets: [u8; STL_NUMBER_FACETS_BYTES] =
+        bytes[STL_HEADER_BYTES..prefix].try_into().unwrap();
+    let num_facets = u32::from_le_bytes(num_facets);
+    let payload = &bytes[prefix..];
+    let trailing = payload.len() % STL_FACET_RECORD_BYTES;
+    if trailing != 0 {
+        return Err(StlError::MisalignedPayload {
+            offset: bytes.len() - trailing,
+            trailing,
+        });
+    }
+    let actual = payload.len() / STL_FACET_RECORD_BYTES;
+    if actual != num_facets as usize {
+        return Err(StlError::FacetCountMismatch {
+            expected: num_facets,
+            actual,
+        });
     }
     Ok((payload, num_facets))
 }
@@ -128,25 +530,250 @@ fn extract_data(bytes: &[u8]) -> Result<(Vec<u8>, u32), InvalidFileContentError>
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::linalg::Vec3;
 
     #[test]
     fn test_cube_bytes_to_vertices() {
         let cube = std::fs::read("tests/files/cube.stl").unwrap();
 
-        let (mut vertices, num_vertices) = get_data(&cube).unwrap();
-        vertices.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+        let mesh = get_data(&cube, 0.0).unwrap();
 
-        assert_eq!(num_vertices, 36);
-        assert!(vertices.first().unwrap().le(&1.0));
+        assert_eq!(mesh.indices.len(), 36);
+        assert_eq!(mesh.positions.len(), 8 * STL_AXES);
+        let bounds = mesh.bounds().unwrap();
+        assert_eq!(bounds.min, Vec3::from([-0.5, -0.5, 0.0]));
+        assert_eq!(bounds.max, Vec3::from([0.5, 0.5, 1.0]));
     }
 
     #[test]
     fn test_box_bytes_to_vertices() {
         let cube = std::fs::read("tests/files/box.stl").unwrap();
 
-        let (mut vertices, _) = get_data(&cube).unwrap();
-        vertices.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+        let mesh = get_data(&cube, 0.0).unwrap();
+
+        let bounds = mesh.bounds().unwrap();
+        assert_eq!(bounds.min, Vec3::from([-130.0, -30.0, 0.0]));
+        assert_eq!(bounds.max, Vec3::from([30.0, 30.0, 50.0]));
+    }
+
+    #[test]
+    fn test_ascii_cube_matches_binary() {
+        let binary = std::fs::read("tests/files/cube.stl").unwrap();
+        let ascii = std::fs::read("tests/files/cube_ascii.stl").unwrap();
+
+        assert_eq!(
+            get_data(&ascii, 0.0).unwrap(),
+            get_data(&binary, 0.0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_binary_with_solid_header() {
+        let mut cube = std::fs::read("tests/files/cube.stl").unwrap();
+        cube[..STL_ASCII_SOLID.len()].copy_from_slice(STL_ASCII_SOLID.as_bytes());
+
+        let mesh = get_data(&cube, 0.0).unwrap();
+
+        assert_eq!(mesh.indices.len(), 36);
+    }
+
+    #[test]
+    fn test_damaged_binary_with_solid_header() {
+        let mut cube = std::fs::read("tests/files/cube.stl").unwrap();
+        cube[..STL_ASCII_SOLID.len()].copy_from_slice(STL_ASCII_SOLID.as_bytes());
+
+        let err = get_data(&cube[..cube.len() - 1], 0.0).unwrap_err();
+        assert!(matches!(err, StlError::MisalignedPayload { .. }));
+
+        // The facet count puts NUL bytes into the first line above, here it
+        // ends early and they follow it.
+        let mut damaged = cube[..STL_HEADER_BYTES].to_vec();
+        damaged.fill(b'x');
+        damaged[..STL_ASCII_SOLID.len()].copy_from_slice(STL_ASCII_SOLID.as_bytes());
+        damaged[STL_ASCII_SOLID.len()] = b'\n';
+        damaged[STL_HEADER_BYTES - 1] = 0xff;
+        damaged.extend(&cube[STL_HEADER_BYTES..cube.len() - STL_FACET_RECORD_BYTES]);
+        let err = get_data(&damaged, 0.0).unwrap_err();
+        assert_eq!(
+            err,
+            StlError::FacetCountMismatch {
+                expected: 12,
+                actual: 11
+            }
+        );
+    }
+
+    #[test]
+    fn test_ascii_syntax_error() {
+        let ascii = std::fs::read_to_string("tests/files/cube_ascii.stl").unwrap();
+        let ascii = ascii.replacen("outer loop", "outer lop", 1);
+
+        let err = get_data(ascii.as_bytes(), 0.0).unwrap_err();
+
+        assert!(matches!(
+            err,
+            StlError::AsciiSyntax {
+                line: 3,
+                column: 11,
+                expected: "loop",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_ascii_with_windows_1252_name() {
+        let ascii = std::fs::read("tests/files/cube_ascii.stl").unwrap();
+        let start = ascii.iter().position(|&b| b == b'\n').unwrap();
+        let mut renamed = b"solid Teil_\xe4".to_vec();
+        renamed.extend(&ascii[start..]);
+
+        let file = parse(&renamed).unwrap();
+
+        assert_eq!(file.header, "Teil_\u{fffd}");
+        assert_eq!(file.num_facets(), 12);
+        // Bytes which are not UTF-8 outside the name are syntax errors.
+        let broken = [&renamed[..], b"solid \xe4\n\xe4"].concat();
+        assert!(matches!(
+            parse(&broken).unwrap_err(),
+            StlError::AsciiSyntax { found: Some(found), .. } if found == "\u{fffd}"
+        ));
+    }
+
+    #[test]
+    fn test_ascii_with_byte_order_mark() {
+        let ascii = std::fs::read("tests/files/cube_ascii.stl").unwrap();
+        let with_bom = [UTF8_BOM, &ascii].concat();
+
+        assert_eq!(parse(&with_bom).unwrap(), parse(&ascii).unwrap());
+    }
+
+    #[test]
+    fn test_truncated_header() {
+        let cube = std::fs::read("tests/files/cube.stl").unwrap();
+
+        let err = get_data(&cube[..STL_HEADER_BYTES], 0.0).unwrap_err();
+
+        assert_eq!(err, StlError::TruncatedHeader { len: 80 });
+    }
+
+    #[test]
+    fn test_misaligned_payload() {
+        let cube = std::fs::read("tests/files/cube.stl").unwrap();
+
+        let err = get_data(&cube[..cube.len() - 1], 0.0).unwrap_err();
+
+        assert_eq!(
+            err,
+            StlError::MisalignedPayload {
+                offset: 634,
+                trailing: 49
+            }
+        );
+    }
+
+    #[test]
+    fn test_facet_count_mismatch() {
+        let cube = std::fs::read("tests/files/cube.stl").unwrap();
+
+        let err = get_data(&cube[..cube.len() - STL_FACET_RECORD_BYTES], 0.0).unwrap_err();
+
+        assert_eq!(
+            err,
+            StlError::FacetCountMismatch {
+                expected: 12,
+                actual: 11
+            }
+        );
+    }
+
+    #[test]
+    fn test_non_finite_coordinate() {
+        let mut cube = std::fs::read("tests/files/cube.stl").unwrap();
+        let offset = STL_HEADER_BYTES + STL_NUMBER_FACETS_BYTES + STL_FACET_RECORD_BYTES + 16;
+        cube[offset..offset + STL_F32_BYTES].copy_from_slice(&f32::NAN.to_le_bytes());
+
+        let err = get_data(&cube, 0.0).unwrap_err();
+
+        assert!(matches!(
+            err,
+            StlError::NonFiniteCoordinate { offset: 150, .. }
+        ));
+    }
+
+    /// Cube whose facets are coloured through `attribute`, under `header`.
+    fn colored_cube(header: &[u8], attribute: impl Fn(usize) -> u16) -> Vec<u8> {
+        let mut cube = std::fs::read("tests/files/cube.stl").unwrap();
+        cube[..STL_HEADER_BYTES].fill(b' ');
+        cube[..header.len()].copy_from_slice(header);
+        for idx in 0..12 {
+            let offset =
+                STL_HEADER_BYTES + STL_NUMBER_FACETS_BYTES + (idx + 1) * STL_FACET_RECORD_BYTES
+                    - STL_EXTRA_BYTES;
+            cube[offset..offset + STL_EXTRA_BYTES].copy_from_slice(&attribute(idx).to_le_bytes());
+        }
+        cube
+    }
+
+    #[test]
+    fn test_viscam_colors() {
+        // Pure red on the first facet only, blue is in the low bits.
+        let cube = colored_cube(b"", |idx| if idx == 0 { 0x8000 | 0x1f << 10 } else { 0 });
+
+        let file = parse(&cube).unwrap();
+        assert_eq!(file.colors.len(), 12);
+        assert_eq!(file.colors[0], Some([255, 0, 0]));
+        assert!(file.colors[1..].iter().all(Option::is_none));
+
+        let mesh = file.to_mesh(0.0);
+        assert_eq!(
+            mesh.colors[..6],
+            [
+                1.0,
+                0.0,
+                0.0,
+                DEFAULT_COLOR[0],
+                DEFAULT_COLOR[1],
+                DEFAULT_COLOR[2]
+            ]
+        );
+
+        // Writing keeps the colours, also those of Magics files which are
+        // written in the VisCAM convention.
+        assert_eq!(parse(&write_binary(&file)).unwrap(), file);
+        let mut header = b"COLOR=".to_vec();
+        header.extend([0, 255, 0, 255]);
+        let magics = parse(&colored_cube(
+            &header,
+            |idx| {
+                if idx < 6 {
+                    0x8000
+                } else {
+                    0x10
+                }
+            },
+        ))
+        .unwrap();
+        let written = parse(&write_binary(&magics)).unwrap();
+        assert_eq!(written.colors[0], Some([0, 255, 0]));
+        assert_eq!(written.colors, magics.colors);
+        assert!(!written.header.contains("COLOR="));
+
+        // Without the flag the attribute bytes are no colour at all.
+        let plain = colored_cube(b"", |_| 0x1f);
+        assert!(parse(&plain).unwrap().colors.is_empty());
+    }
+
+    #[test]
+    fn test_magics_colors() {
+        // The object is green, facets with the flag cleared have their own
+        // colour with red in the low bits.
+        let mut header = b"COLOR=".to_vec();
+        header.extend([0, 255, 0, 255]);
+        let cube = colored_cube(&header, |idx| if idx < 6 { 0x8000 } else { 0x10 });
 
-        assert!(vertices.first().unwrap().le(&1.0));
+        let file = parse(&cube).unwrap();
+        assert_eq!(file.colors[0], Some([0, 255, 0]));
+        assert_eq!(file.colors[6], Some([132, 0, 0]));
     }
 }
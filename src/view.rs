@@ -0,0 +1,880 @@
+use crate::keymap::Action;
+use crate::linalg::{Quaternion, Sphere, Vec3, VecOps};
+use std::f32::consts::PI;
+
+/// Wheel delta that zooms by a factor of e.
+pub const WHEEL_DRAG: f32 = 512.0;
+/// Share of the rotation velocity kept on every frame once a drag ended.
+pub const AMORTIZATION: f32 = 0.95;
+/// Vertical field of view in radians.
+pub const FIELD_OF_VIEW: f32 = 45.0 * PI / 180.0;
+/// Closest and farthest the camera gets to its target, in radii of the scene.
+pub const MIN_DISTANCE: f32 = 0.01;
+pub const MAX_DISTANCE: f32 = 20.0;
+/// Angle turned by a single orbit step.
+pub const ORBIT_STEP: f32 = 15.0 * PI / 180.0;
+/// Default threshold for feature edges.
+pub const FEATURE_ANGLE: f32 = 30.0 * PI / 180.0;
+/// Default length of animated camera moves in seconds.
+pub const TRANSITION_DURATION: f32 = 0.4;
+/// Smallest ratio between the near and the far plane, which bounds the loss
+/// of depth precision when the camera is inside the scene.
+pub const NEAR_RATIO: f32 = 1e-3;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Projection {
+    #[default]
+    Perspective,
+    /// Parallel projection showing as much of the model at the depth of the
+    /// target as the perspective one would, so zooming works the same.
+    Orthographic,
+}
+
+/// Orbit camera looking at a target point of the model.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub distance: f32,
+    /// Rotation of the model in view space.
+    pub rotation: Quaternion,
+    /// Point in model space shown at the centre of the viewport.
+    pub target: Vec3<f32>,
+    pub projection: Projection,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::fit(&Sphere::default())
+    }
+}
+
+impl Camera {
+    /// Looks at the sphere from the front, close enough for it to fill the
+    /// height of the viewport.
+    pub fn fit(scene: &Sphere) -> Self {
+        Self {
+            distance: scene.radius / (FIELD_OF_VIEW * 0.5).sin(),
+            rotation: Quaternion::IDENTITY,
+            target: scene.center,
+            projection: Projection::default(),
+        }
+    }
+
+    /// Position of the camera in model space.
+    pub fn eye(&self) -> Vec3<f32> {
+        let offset = Vec3::from([0.0, 0.0, self.distance]);
+        self.target + self.rotation.conjugate().rotate(offset)
+    }
+
+    /// Column-major matrix from model to view space.
+    pub fn view_matrix(&self) -> [f32; 16] {
+        let mut matrix = self.rotation.to_matrix();
+        let translation = self.rotation.rotate(self.target.scale(-1.0));
+        let [x, y, z]: [f32; 3] = translation.into();
+        matrix[12] = x;
+        matrix[13] = y;
+        matrix[14] = z - self.distance;
+        matrix
+    }
+
+    /// Near and far plane enclosing the scene as tightly as possible. Only
+    /// the orthographic projection can look behind the camera, so the near
+    /// plane may be negative there.
+    pub fn clip_planes(&self, scene: &Sphere) -> (f32, f32) {
+        let center_distance = (self.eye() - scene.center).length();
+        let far = center_distance + scene.radius;
+        let near = center_distance - scene.radius;
+        match self.projection {
+            Projection::Perspective => (near.max(far * NEAR_RATIO), far),
+            Projection::Orthographic => (near, far),
+        }
+    }
+
+    /// Column-major projection for a viewport of the given aspect ratio.
+    pub fn projection_matrix(&self, aspect_ratio: f32, scene: &Sphere) -> [f32; 16] {
+        let (near, far) = self.clip_planes(scene);
+        let depth = 1.0 / (near - far);
+        match self.projection {
+            Projection::Perspective => {
+                let f = 1.0 / (FIELD_OF_VIEW * 0.5).tan();
+                [
+                    f / aspect_ratio,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    f,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    (far + near) * depth,
+                    -1.0,
+                    0.0,
+                    0.0,
+                    2.0 * far * near * depth,
+                    0.0,
+                ]
+            }
+            Projection::Orthographic => {
+                let half_height = self.distance * (FIELD_OF_VIEW * 0.5).tan();
+                [
+                    1.0 / (half_height * aspect_ratio),
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    1.0 / half_height,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    2.0 * depth,
+                    0.0,
+                    0.0,
+                    0.0,
+                    (far + near) * depth,
+                    1.0,
+                ]
+            }
+        }
+    }
+
+    /// Size of a pixel at the depth of the target.
+    fn units_per_pixel(&self, viewport_height: f32) -> f32 {
+        2.0 * self.distance * (FIELD_OF_VIEW * 0.5).tan() / viewport_height
+    }
+
+    /// Offset from the target in view space of the point under a pixel, at
+    /// the depth of the target.
+    fn view_offset(&self, [x, y]: [f32; 2], [width, height]: [f32; 2]) -> Vec3<f32> {
+        Vec3::from([x - width * 0.5, height * 0.5 - y, 0.0]).scale(self.units_per_pixel(height))
+    }
+
+    /// Applies a rotation given in view space on top of the current one.
+    fn rotate(&mut self, rotation: Quaternion) {
+        self.rotation = (rotation * self.rotation).normalize();
+    }
+
+    /// Moves the target so the model follows the pointer, a pixel at the
+    /// depth of the target maps to a pixel on screen.
+    fn pan(&mut self, [dx, dy]: [f32; 2], viewport_height: f32) {
+        let offset = Vec3::from([dx, -dy, 0.0]).scale(self.units_per_pixel(viewport_height));
+        self.target = self.target - self.rotation.conjugate().rotate(offset);
+    }
+}
+
+/// Views along the axes of the model, taking `z` as up as is usual for STL
+/// files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StandardView {
+    Front,
+    Back,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    /// Isometric view from the front right top corner.
+    Iso,
+}
+
+impl StandardView {
+    pub fn rotation(self) -> Quaternion {
+        let x = Vec3::from([1.0, 0.0, 0.0]);
+        let y = Vec3::from([0.0, 1.0, 0.0]);
+        // Looking along `y`, i.e. the model's `z` turned up.
+        let front = Quaternion::from_axis_angle(x, -PI / 2.0);
+        match self {
+            StandardView::Front => front,
+            StandardView::Back => Quaternion::from_axis_angle(y, PI) * front,
+            StandardView::Left => Quaternion::from_axis_angle(y, PI / 2.0) * front,
+            StandardView::Right => Quaternion::from_axis_angle(y, -PI / 2.0) * front,
+            StandardView::Top => Quaternion::IDENTITY,
+            StandardView::Bottom => Quaternion::from_axis_angle(x, PI),
+            StandardView::Iso => {
+                Quaternion::from_axis_angle(x, (0.5_f32).sqrt().atan())
+                    * Quaternion::from_axis_angle(y, -PI / 4.0)
+                    * front
+            }
+        }
+    }
+}
+
+/// Direction the camera moves around the target in a single orbit step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orbit {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+impl Orbit {
+    /// The rotation of the model in view space, which is opposite to the
+    /// motion of the camera.
+    fn rotation(self) -> Quaternion {
+        let (axis, angle) = match self {
+            Orbit::Left => ([0.0, 1.0, 0.0], ORBIT_STEP),
+            Orbit::Right => ([0.0, 1.0, 0.0], -ORBIT_STEP),
+            Orbit::Up => ([1.0, 0.0, 0.0], ORBIT_STEP),
+            Orbit::Down => ([1.0, 0.0, 0.0], -ORBIT_STEP),
+        };
+        Quaternion::from_axis_angle(Vec3::from(axis), angle)
+    }
+}
+
+/// What dragging the pointer does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DragMode {
+    #[default]
+    Rotate,
+    Pan,
+}
+
+/// Pointer state between events.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Interaction {
+    /// Id and last position of every pressed pointer, in the order they went
+    /// down.
+    pub pointers: Vec<(i32, [f32; 2])>,
+    /// What a single pointer does, two pointers always pinch.
+    pub drag_mode: DragMode,
+    /// Angular velocity in radians per frame as a scaled axis, taken from the
+    /// last move and kept going as inertia on release.
+    pub velocity: Vec3<f32>,
+}
+
+/// How the surface of the model is drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    #[default]
+    Solid,
+    /// Every triangle edge, to inspect the density of the tessellation.
+    Wireframe,
+    SolidWithEdges,
+    /// Only edges where the surface bends sharply, see
+    /// [`crate::mesh::Mesh::feature_edges`].
+    FeatureEdges,
+}
+
+impl RenderMode {
+    pub fn next(self) -> Self {
+        match self {
+            RenderMode::Solid => RenderMode::Wireframe,
+            RenderMode::Wireframe => RenderMode::SolidWithEdges,
+            RenderMode::SolidWithEdges => RenderMode::FeatureEdges,
+            RenderMode::FeatureEdges => RenderMode::Solid,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayOptions {
+    pub smooth_shading: bool,
+    pub render_mode: RenderMode,
+    /// Smallest angle in radians between two facets for their shared edge to
+    /// count as a feature edge.
+    pub feature_angle: f32,
+    /// Ground grid under the model.
+    pub grid: bool,
+    /// Axis gizmo in the corner of the viewport.
+    pub axes: bool,
+    /// Bounding box with its dimensions.
+    pub bounding_box: bool,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        Self {
+            smooth_shading: false,
+            render_mode: RenderMode::default(),
+            feature_angle: FEATURE_ANGLE,
+            grid: true,
+            axes: true,
+            bounding_box: false,
+        }
+    }
+}
+
+/// Animated move of the camera, eased in and out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    pub from: Camera,
+    pub to: Camera,
+    /// Seconds since the start.
+    pub elapsed: f32,
+}
+
+impl Transition {
+    /// Camera at `progress` between 0 and 1. The distance is interpolated
+    /// geometrically so the zoom speed looks constant.
+    fn camera(&self, progress: f32) -> Camera {
+        let t = ease_in_out(progress);
+        let (from, to) = (self.from, self.to);
+        Camera {
+            distance: from.distance * (to.distance / from.distance).powf(t),
+            rotation: from.rotation.slerp(to.rotation, t),
+            target: from.target + (to.target - from.target).scale(t),
+            projection: to.projection,
+        }
+    }
+}
+
+/// Everything the viewer needs to draw a frame besides the mesh itself. The
+/// event handlers only mutate this and the renderer only reads it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ViewerState {
+    pub camera: Camera,
+    pub interaction: Interaction,
+    pub display: DisplayOptions,
+    /// Bounding sphere of the model, which the camera frames and stays
+    /// around.
+    pub scene: Sphere,
+    pub transition: Option<Transition>,
+    /// Length of animated camera moves in seconds, zero turns them off.
+    pub transition_duration: f32,
+}
+
+impl Default for ViewerState {
+    fn default() -> Self {
+        Self {
+            camera: Camera::default(),
+            interaction: Interaction::default(),
+            display: DisplayOptions::default(),
+            scene: Sphere::default(),
+            transition: None,
+            transition_duration: TRANSITION_DURATION,
+        }
+    }
+}
+
+impl ViewerState {
+    /// Frames a new model.
+    pub fn set_scene(&mut self, mut scene: Sphere) {
+        // A single point has no size to frame, show it at unit scale instead.
+        if scene.radius <= 0.0 {
+            scene.radius = 1.0;
+        }
+        self.scene = scene;
+        self.reset_camera();
+    }
+
+    /// Puts the camera back to its initial position at once and stops any
+    /// motion, keeping the projection.
+    pub fn reset_camera(&mut self) {
+        self.camera = self.fitted_camera();
+        self.interaction = Interaction::default();
+        self.transition = None;
+    }
+
+    /// Moves the camera to the result of the action, animated if a
+    /// transition duration is set.
+    pub fn apply(&mut self, action: Action) {
+        // Further moves start from where a running one is going.
+        let mut camera = self.transition.map_or(self.camera, |t| t.to);
+        match action {
+            Action::View(view) => {
+                camera = Camera {
+                    rotation: view.rotation(),
+                    ..self.fitted_camera()
+                }
+            }
+            Action::ResetView => camera = self.fitted_camera(),
+            Action::FitToModel => {
+                let fitted = Camera::fit(&self.scene);
+                camera.target = fitted.target;
+                camera.distance = fitted.distance;
+            }
+            Action::RenderMode(mode) => self.display.render_mode = mode,
+            Action::NextRenderMode => self.display.render_mode = self.display.render_mode.next(),
+            Action::ToggleShading => self.display.smooth_shading = !self.display.smooth_shading,
+            Action::ToggleGrid => self.display.grid = !self.display.grid,
+            Action::ToggleAxes => self.display.axes = !self.display.axes,
+            Action::ToggleBoundingBox => self.display.bounding_box = !self.display.bounding_box,
+            Action::ToggleProjection => {
+                let projection = match self.camera.projection {
+                    Projection::Perspective => Projection::Orthographic,
+                    Projection::Orthographic => Projection::Perspective,
+                };
+                self.camera.projection = projection;
+                camera.projection = projection;
+            }
+            Action::Orbit(orbit) => camera.rotate(orbit.rotation()),
+        }
+        self.animate_to(camera);
+    }
+
+    /// Zooms exponentially towards the point under the pointer, which stays
+    /// in place on screen.
+    pub fn wheel(&mut self, delta_y: f32, position: [f32; 2], viewport: [f32; 2]) {
+        self.transition = None;
+        self.zoom((-delta_y / WHEEL_DRAG).exp(), position, viewport);
+    }
+
+    /// Starts a drag at `position` in pixels from the top left corner. A
+    /// second pointer turns the drag into a pinch.
+    pub fn pointer_down(&mut self, id: i32, position: [f32; 2], mode: DragMode) {
+        // Grabbing the model stops it where it is.
+        self.transition = None;
+        let pointers = &mut self.interaction.pointers;
+        pointers.retain(|(pointer, _)| *pointer != id);
+        pointers.push((id, position));
+        if pointers.len() == 1 {
+            self.interaction.drag_mode = mode;
+        }
+        self.interaction.velocity = Vec3::default();
+    }
+
+    pub fn pointer_up(&mut self, id: i32) {
+        self.interaction
+            .pointers
+            .retain(|(pointer, _)| *pointer != id);
+    }
+
+    /// A single pointer either turns the model as if it dragged a ball
+    /// filling the viewport or moves the model along. Two pointers pan with
+    /// their midpoint and zoom with their spread.
+    pub fn pointer_move(&mut self, id: i32, position: [f32; 2], viewport: [f32; 2]) {
+        let pointers = &mut self.interaction.pointers;
+        let idx = match pointers.iter().position(|(pointer, _)| *pointer == id) {
+            Some(idx) => idx,
+            None => return,
+        };
+        let last = std::mem::replace(&mut pointers[idx].1, position);
+
+        match pointers.len() {
+            1 if self.interaction.drag_mode == DragMode::Pan => {
+                self.camera.pan(sub(position, last), viewport[1]);
+            }
+            1 => {
+                let rotation = Quaternion::from_rotation_arc(
+                    arcball_point(last, viewport),
+                    arcball_point(position, viewport),
+                );
+                self.interaction.velocity = rotation.to_scaled_axis();
+                self.camera.rotate(rotation);
+            }
+            2 => {
+                let other = pointers[1 - idx].1;
+                let midpoint = |a: [f32; 2]| [(a[0] + other[0]) * 0.5, (a[1] + other[1]) * 0.5];
+                let spread =
+                    |a: [f32; 2]| Vec3::from([a[0] - other[0], a[1] - other[1], 0.0]).length();
+
+                self.camera
+                    .pan(sub(midpoint(position), midpoint(last)), viewport[1]);
+                if spread(position) > 0.0 {
+                    let factor = spread(last) / spread(position);
+                    self.zoom(factor, midpoint(position), viewport);
+                }
+            }
+            // More fingers than that are just resting on the screen.
+            _ => {}
+        }
+    }
+
+    /// Initial camera for the scene in the current projection.
+    fn fitted_camera(&self) -> Camera {
+        Camera {
+            projection: self.camera.projection,
+            ..Camera::fit(&self.scene)
+        }
+    }
+
+    fn animate_to(&mut self, camera: Camera) {
+        self.interaction.velocity = Vec3::default();
+        if self.transition_duration > 0.0 && camera != self.camera {
+            self.transition = Some(Transition {
+                from: self.camera,
+                to: camera,
+                elapsed: 0.0,
+            });
+        } else {
+            self.camera = camera;
+            self.transition = None;
+        }
+    }
+
+    /// Scales the distance to the target by `factor` within the limits of the
+    /// scene, keeping the point under `position` in place.
+    fn zoom(&mut self, factor: f32, position: [f32; 2], viewport: [f32; 2]) {
+        let camera = &mut self.camera;
+        let distance = (camera.distance * factor).clamp(
+            self.scene.radius * MIN_DISTANCE,
+            self.scene.radius * MAX_DISTANCE,
+        );
+        let offset = camera
+            .view_offset(position, viewport)
+            .scale(1.0 - distance / camera.distance);
+        camera.target = camera.target + camera.rotation.conjugate().rotate(offset);
+        camera.distance = distance;
+    }
+
+    /// Advances the state by one frame that took `elapsed` seconds, moving
+    /// along a transition or letting the rotation run out after a drag.
+    pub fn tick(&mut self, elapsed: f32) {
+        if let Some(transition) = self.transition.as_mut() {
+            transition.elapsed += elapsed;
+            let progress = (transition.elapsed / self.transition_duration).min(1.0);
+            self.camera = transition.camera(progress);
+            if progress >= 1.0 {
+                self.transition = None;
+            }
+            return;
+        }
+        if !self.interaction.pointers.is_empty() {
+            return;
+        }
+        self.interaction.velocity = self.interaction.velocity.scale(AMORTIZATION);
+        self.camera
+            .rotate(Quaternion::from_scaled_axis(self.interaction.velocity));
+    }
+}
+
+/// Cubic ease in and out of `t` between 0 and 1.
+fn ease_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (2.0 - 2.0 * t).powi(3) * 0.5
+    }
+}
+
+fn sub(lhs: [f32; 2], rhs: [f32; 2]) -> [f32; 2] {
+    [lhs[0] - rhs[0], lhs[1] - rhs[1]]
+}
+
+/// Projects a pixel position onto the arcball, a unit sphere around the
+/// centre of the viewport that blends into a hyperbolic sheet towards the
+/// edges so rotation stays continuous when dragging outside of it.
+fn arcball_point([x, y]: [f32; 2], [width, height]: [f32; 2]) -> Vec3<f32> {
+    let radius = width.min(height) * 0.5;
+    let x = (x - width * 0.5) / radius;
+    let y = (height * 0.5 - y) / radius;
+    let squared = x * x + y * y;
+    let z = if squared <= 0.5 {
+        (1.0 - squared).sqrt()
+    } else {
+        0.5 / squared.sqrt()
+    };
+    Vec3::from([x, y, z]).normalize()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const VIEWPORT: [f32; 2] = [400.0, 300.0];
+    const FRAME: f32 = 1.0 / 60.0;
+    const CENTER: [f32; 2] = [200.0, 150.0];
+
+    #[test]
+    fn test_drag_rotates_with_inertia() {
+        let mut state = ViewerState::default();
+        state.pointer_move(0, [300.0, 150.0], VIEWPORT);
+        assert_eq!(state.camera, Camera::default());
+
+        // Dragging right turns the front of the model to the right.
+        state.pointer_down(0, CENTER, DragMode::Rotate);
+        state.pointer_move(0, [210.0, 150.0], VIEWPORT);
+        let front = state.camera.rotation.rotate(Vec3::from([0.0, 0.0, 1.0]));
+        assert!(*front.get(0).unwrap() > 0.0);
+        assert_eq!(*front.get(1).unwrap(), 0.0);
+
+        // Holding still while dragging must not keep spinning.
+        let rotation = state.camera.rotation;
+        state.tick(FRAME);
+        assert_eq!(state.camera.rotation, rotation);
+
+        state.pointer_up(0);
+        let speed = state.interaction.velocity.length();
+        state.tick(FRAME);
+        assert!((state.interaction.velocity.length() - speed * AMORTIZATION).abs() < 1e-6);
+        assert_ne!(state.camera.rotation, rotation);
+        for _ in 0..1000 {
+            state.tick(FRAME);
+        }
+        assert!(state.interaction.velocity.length() < 1e-6);
+    }
+
+    #[test]
+    fn test_drag_past_the_top_keeps_orbiting() {
+        let mut state = ViewerState::default();
+        // Turn the model upside down, then keep dragging the same way.
+        for _ in 0..2 {
+            state.pointer_down(0, [CENTER[0], 0.0], DragMode::Rotate);
+            state.pointer_move(0, [CENTER[0], 300.0], VIEWPORT);
+            state.pointer_up(0);
+        }
+        let up = state.camera.rotation.rotate(Vec3::from([0.0, 1.0, 0.0]));
+        assert!(*up.get(1).unwrap() < 0.0);
+
+        state.pointer_down(0, [CENTER[0], 0.0], DragMode::Rotate);
+        state.pointer_move(0, [CENTER[0], 300.0], VIEWPORT);
+        let up_after = state.camera.rotation.rotate(Vec3::from([0.0, 1.0, 0.0]));
+        assert!((up_after - up).length() > 0.1);
+    }
+
+    #[test]
+    fn test_pan_follows_pointer() {
+        let mut state = ViewerState::default();
+        state.pointer_down(0, CENTER, DragMode::Pan);
+        state.pointer_move(0, [CENTER[0] + 30.0, CENTER[1]], VIEWPORT);
+        state.pointer_up(0);
+        let near = state.camera.target;
+        assert!(*near.get(0).unwrap() < 0.0);
+        assert_eq!(*near.get(1).unwrap(), 0.0);
+        assert_eq!(state.interaction.velocity, Vec3::default());
+
+        // The same drag covers twice the distance from twice as far away.
+        state.reset_camera();
+        state.camera.distance *= 2.0;
+        state.pointer_down(0, CENTER, DragMode::Pan);
+        state.pointer_move(0, [CENTER[0] + 30.0, CENTER[1]], VIEWPORT);
+        assert!((state.camera.target - near.scale(2.0)).length() < 1e-6);
+
+        // Panning is relative to the screen, not the model.
+        state.reset_camera();
+        state.camera.rotation = Quaternion::from_axis_angle(Vec3::from([0.0, 1.0, 0.0]), PI);
+        state.pointer_down(0, CENTER, DragMode::Pan);
+        state.pointer_move(0, [CENTER[0] + 30.0, CENTER[1]], VIEWPORT);
+        assert!((state.camera.target + near).length() < 1e-6);
+    }
+
+    #[test]
+    fn test_pinch_zooms_and_pans() {
+        let mut state = ViewerState::default();
+        let fitted = state.camera;
+        state.pointer_down(1, [150.0, 150.0], DragMode::Rotate);
+        state.pointer_down(2, [250.0, 150.0], DragMode::Rotate);
+
+        // Spreading the fingers symmetrically zooms in around the centre.
+        state.pointer_move(1, [100.0, 150.0], VIEWPORT);
+        state.pointer_move(2, [300.0, 150.0], VIEWPORT);
+        assert_eq!(state.camera.rotation, fitted.rotation);
+        assert!(state.camera.distance < fitted.distance * 0.6);
+        assert!(state.camera.target.length() < 1e-5);
+
+        // Moving both fingers together pans without zooming.
+        let distance = state.camera.distance;
+        state.pointer_move(1, [100.0, 200.0], VIEWPORT);
+        state.pointer_move(2, [300.0, 200.0], VIEWPORT);
+        assert!((state.camera.distance - distance).abs() < 1e-5);
+        assert!(*state.camera.target.get(1).unwrap() > 0.0);
+
+        // Lifting one finger goes back to rotating, from where it rests.
+        state.pointer_up(1);
+        state.pointer_move(2, [310.0, 200.0], VIEWPORT);
+        assert_ne!(state.camera.rotation, fitted.rotation);
+        assert!(state.interaction.velocity.length() < 0.1);
+
+        state.pointer_up(2);
+        assert!(state.interaction.pointers.is_empty());
+    }
+
+    #[test]
+    fn test_orthographic_keeps_framing() {
+        let scene = Sphere::default();
+        let mut state = ViewerState::default();
+        state.wheel(100.0, CENTER, VIEWPORT);
+
+        // A point on the top edge of the viewport at the depth of the target.
+        let camera = state.camera;
+        let top = camera.target + camera.view_offset([CENTER[0], 0.0], VIEWPORT);
+        let project = |camera: &Camera| {
+            let [x, y, z]: [f32; 3] = top.into();
+            let view = camera.view_matrix();
+            let projection = camera.projection_matrix(VIEWPORT[0] / VIEWPORT[1], &scene);
+            let eye: Vec<f32> = (0..4)
+                .map(|row| view[row] * x + view[4 + row] * y + view[8 + row] * z + view[12 + row])
+                .collect();
+            let clip: Vec<f32> = (0..4)
+                .map(|row| (0..4).map(|col| projection[col * 4 + row] * eye[col]).sum())
+                .collect();
+            [clip[0] / clip[3], clip[1] / clip[3], clip[2] / clip[3]]
+        };
+        let perspective = project(&state.camera);
+        state.apply(Action::ToggleProjection);
+        assert_eq!(state.camera.projection, Projection::Orthographic);
+        let orthographic = project(&state.camera);
+        assert!((perspective[1] - 1.0).abs() < 1e-5);
+        assert!((orthographic[1] - 1.0).abs() < 1e-5);
+        assert!(orthographic[2].abs() < 1.0);
+        state.apply(Action::View(StandardView::Front));
+        assert_eq!(state.camera.projection, Projection::Orthographic);
+
+        // Zoomed into the scene, the orthographic view still sees all of it.
+        state.camera.distance = 0.1;
+        let (near, _) = state.camera.clip_planes(&scene);
+        assert!(near < 0.0);
+    }
+
+    #[test]
+    fn test_standard_views() {
+        let eye = |view: StandardView| {
+            let camera = Camera {
+                rotation: view.rotation(),
+                ..Camera::default()
+            };
+            let [x, y, z]: [f32; 3] = camera.eye().normalize().into();
+            [x, y, z].map(|v| (v * 1e4).round() / 1e4)
+        };
+        assert_eq!(eye(StandardView::Front), [0.0, -1.0, 0.0]);
+        assert_eq!(eye(StandardView::Back), [0.0, 1.0, 0.0]);
+        assert_eq!(eye(StandardView::Left), [-1.0, 0.0, 0.0]);
+        assert_eq!(eye(StandardView::Right), [1.0, 0.0, 0.0]);
+        assert_eq!(eye(StandardView::Top), [0.0, 0.0, 1.0]);
+        assert_eq!(eye(StandardView::Bottom), [0.0, 0.0, -1.0]);
+        let corner = (1.0_f32 / 3.0).sqrt();
+        let corner = (corner * 1e4).round() / 1e4;
+        assert_eq!(eye(StandardView::Iso), [corner, -corner, corner]);
+
+        // Up on screen is up in the model for the side views.
+        let up = StandardView::Right
+            .rotation()
+            .rotate(Vec3::from([0.0, 0.0, 1.0]));
+        assert!((up - Vec3::from([0.0, 1.0, 0.0])).length() < 1e-6);
+    }
+
+    #[test]
+    fn test_apply_actions() {
+        let mut state = ViewerState {
+            transition_duration: 0.0,
+            ..ViewerState::default()
+        };
+        state.apply(Action::View(StandardView::Front));
+        state.apply(Action::Orbit(Orbit::Right));
+        state.apply(Action::Orbit(Orbit::Right));
+        // Orbiting right moves the camera towards the right side.
+        let [x, y, _]: [f32; 3] = state.camera.eye().into();
+        assert!(x > 0.0 && y < 0.0);
+
+        state.camera.distance *= 3.0;
+        let rotation = state.camera.rotation;
+        state.apply(Action::FitToModel);
+        assert_eq!(state.camera.rotation, rotation);
+        assert_eq!(state.camera.distance, Camera::default().distance);
+
+        state.apply(Action::NextRenderMode);
+        assert_eq!(state.display.render_mode, RenderMode::Wireframe);
+        state.apply(Action::RenderMode(RenderMode::FeatureEdges));
+        state.apply(Action::NextRenderMode);
+        assert_eq!(state.display.render_mode, RenderMode::Solid);
+        state.apply(Action::ToggleGrid);
+        state.apply(Action::ToggleBoundingBox);
+        assert!(!state.display.grid && state.display.axes && state.display.bounding_box);
+        state.apply(Action::ResetView);
+        assert_eq!(state.camera, Camera::default());
+    }
+
+    #[test]
+    fn test_transitions() {
+        let mut state = ViewerState::default();
+        state.apply(Action::View(StandardView::Front));
+        assert_eq!(state.camera, Camera::default());
+
+        // Orbiting while moving adds to where the move is going.
+        state.apply(Action::Orbit(Orbit::Up));
+        let front = StandardView::Front.rotation();
+        let up = Quaternion::from_axis_angle(Vec3::from([1.0, 0.0, 0.0]), ORBIT_STEP) * front;
+        assert_eq!(state.transition.unwrap().to.rotation, up.normalize());
+
+        state.tick(TRANSITION_DURATION * 0.5);
+        let halfway = state.camera.rotation;
+        assert_ne!(halfway, Quaternion::IDENTITY);
+        assert_ne!(halfway, up.normalize());
+
+        // Grabbing the model stops the transition where it is.
+        state.pointer_down(0, CENTER, DragMode::Rotate);
+        assert_eq!(state.transition, None);
+        state.pointer_up(0);
+        state.tick(TRANSITION_DURATION);
+        assert_eq!(state.camera.rotation, halfway);
+
+        state.apply(Action::ResetView);
+        for _ in 0..100 {
+            state.tick(FRAME);
+        }
+        assert_eq!(state.transition, None);
+        assert!((state.camera.rotation.v - Quaternion::IDENTITY.v).length() < 1e-6);
+        assert!((state.camera.distance - Camera::default().distance).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_ease_in_out() {
+        assert_eq!(ease_in_out(0.0), 0.0);
+        assert_eq!(ease_in_out(0.5), 0.5);
+        assert_eq!(ease_in_out(1.0), 1.0);
+        assert!(ease_in_out(0.1) < 0.1 && ease_in_out(0.9) > 0.9);
+    }
+
+    #[test]
+    fn test_arcball_point() {
+        assert_eq!(arcball_point(CENTER, VIEWPORT), Vec3::from([0.0, 0.0, 1.0]));
+        let outside = arcball_point([2000.0, 150.0], VIEWPORT);
+        assert!((outside.length() - 1.0).abs() < 1e-6);
+        assert!(*outside.get(2).unwrap() > 0.0);
+    }
+
+    #[test]
+    fn test_wheel_zooms_to_cursor() {
+        let mut state = ViewerState::default();
+        state.set_scene(Sphere {
+            center: Vec3::from([10.0, 0.0, 0.0]),
+            radius: 2.0,
+        });
+        let fitted = state.camera;
+        assert_eq!(fitted.target, Vec3::from([10.0, 0.0, 0.0]));
+
+        // The point under the cursor keeps its offset to the camera axis,
+        // scaled by the zoom.
+        let cursor = [300.0, 150.0];
+        let under_cursor = fitted.target + fitted.view_offset(cursor, VIEWPORT);
+        state.wheel(WHEEL_DRAG, cursor, VIEWPORT);
+        let camera = state.camera;
+        assert!((camera.distance - fitted.distance / std::f32::consts::E).abs() < 1e-5);
+        let offset = camera.view_offset(cursor, VIEWPORT);
+        assert!((camera.target + offset - under_cursor).length() < 1e-5);
+
+        // Zooming is bounded by the size of the scene.
+        state.wheel(-1e5, CENTER, VIEWPORT);
+        assert_eq!(state.camera.distance, 2.0 * MAX_DISTANCE);
+        state.wheel(1e5, CENTER, VIEWPORT);
+        assert_eq!(state.camera.distance, 2.0 * MIN_DISTANCE);
+
+        state.display.smooth_shading = true;
+        state.reset_camera();
+        assert_eq!(state.camera, fitted);
+        assert!(state.display.smooth_shading);
+    }
+
+    #[test]
+    fn test_view_and_clip_planes() {
+        let scene = Sphere {
+            center: Vec3::from([1.0, 2.0, 3.0]),
+            radius: 4.0,
+        };
+        let mut camera = Camera::fit(&scene);
+        camera.rotation = Quaternion::from_axis_angle(Vec3::from([1.0, 1.0, 0.0]), 1.0);
+
+        let view = camera.view_matrix();
+        let transform =
+            |[x, y, z]: [f32; 3]| {
+                Vec3::from([0, 1, 2].map(|row| {
+                    view[row] * x + view[4 + row] * y + view[8 + row] * z + view[12 + row]
+                }))
+            };
+        assert!(
+            (transform(camera.target.into()) - Vec3::from([0.0, 0.0, -camera.distance])).length()
+                < 1e-5
+        );
+        assert!(transform(camera.eye().into()).length() < 1e-5);
+
+        let (near, far) = camera.clip_planes(&scene);
+        assert!((near - (camera.distance - 4.0)).abs() < 1e-5);
+        assert!((far - (camera.distance + 4.0)).abs() < 1e-5);
+
+        // Inside the scene the near plane stays in front of the camera.
+        camera.distance = 1.0;
+        let (near, far) = camera.clip_planes(&scene);
+        assert_eq!(near, far * NEAR_RATIO);
+    }
+}
@@ -0,0 +1,95 @@
+use std::process::Command;
+
+fn wedge(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_wedge"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn test_info() {
+    let output = wedge(&["info", "tests/files/cube.stl"]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    assert!(output.status.success());
+    assert!(stdout.contains("WEDGE - TESTFILE - CUBE"));
+    assert!(stdout.contains("facets:       12"));
+    assert!(stdout.contains("volume:       1"));
+}
+
+#[test]
+fn test_validate() {
+    let truncated = std::env::temp_dir().join("wedge-test-truncated.stl");
+    let cube = std::fs::read("tests/files/cube.stl").unwrap();
+    std::fs::write(&truncated, &cube[..cube.len() - 1]).unwrap();
+
+    assert!(wedge(&["validate", "tests/files/cube.stl"])
+        .status
+        .success());
+    let output = wedge(&["validate", truncated.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("STL:"));
+}
+
+#[test]
+fn test_empty_file() {
+    let empty = std::env::temp_dir().join("wedge-test-empty.stl");
+    std::fs::write(&empty, "solid x\nendsolid x\n").unwrap();
+
+    let output = wedge(&["validate", empty.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("no facets"));
+
+    let output = wedge(&["info", empty.to_str().unwrap()]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("surface area: 0\n"));
+    assert!(stdout.contains("volume:       0\n"));
+}
+
+#[test]
+fn test_convert_round_trip() {
+    let dir = std::env::temp_dir();
+    let ascii = dir.join("wedge-test-cube-ascii.stl");
+    let binary = dir.join("wedge-test-cube-binary.stl");
+
+    let output = wedge(&[
+        "convert",
+        "tests/files/cube.stl",
+        ascii.to_str().unwrap(),
+        "--ascii",
+    ]);
+    assert!(output.status.success());
+    let output = wedge(&["convert", ascii.to_str().unwrap(), binary.to_str().unwrap()]);
+    assert!(output.status.success());
+
+    assert_eq!(
+        std::fs::read(&binary).unwrap(),
+        std::fs::read("tests/files/cube.stl").unwrap()
+    );
+}
+
+#[test]
+fn test_thumbnail() {
+    let thumbnail = std::env::temp_dir().join("wedge-test-cube.png");
+
+    let output = wedge(&[
+        "thumbnail",
+        "tests/files/cube.stl",
+        "-o",
+        thumbnail.to_str().unwrap(),
+        "--size",
+        "64",
+    ]);
+    assert!(output.status.success());
+    let png = std::fs::read(&thumbnail).unwrap();
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+    for size in ["0", "4097"] {
+        let output = wedge(&["thumbnail", "tests/files/cube.stl", "--size", size]);
+        assert_eq!(output.status.code(), Some(2));
+    }
+}
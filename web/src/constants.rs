@@ -0,0 +1,54 @@
+use wedge::view::{DisplayOptions, RenderMode};
+
+pub const COMPONENTS_PER_VERTEX: i32 = 3;
+pub const WELD_TOLERANCE: f32 = 1e-5;
+pub const STL_EXTENSION: &str = ".stl";
+pub const DROP_TARGET_CLASS: &str = "drop-target";
+pub const MIDDLE_BUTTON: i16 = 1;
+pub const RIGHT_BUTTON: i16 = 2;
+/// Elements taking keyboard input of their own, where shortcuts are off.
+pub const TEXT_INPUT_TAGS: [&str; 3] = ["INPUT", "SELECT", "TEXTAREA"];
+/// Whether a display option is on.
+pub type Enabled = fn(&DisplayOptions) -> bool;
+/// Id, label, action name and state of every checkbox.
+pub const TOGGLES: [(&str, &str, &str, Enabled); 4] = [
+    ("shading-toggle", "Smooth shading", "shading", |display| {
+        display.smooth_shading
+    }),
+    ("grid-toggle", "Grid", "grid", |display| display.grid),
+    ("axes-toggle", "Axes", "axes", |display| display.axes),
+    (
+        "bounding-box-toggle",
+        "Bounding box",
+        "bounding-box",
+        |display| display.bounding_box,
+    ),
+];
+pub const RENDER_MODE_SELECT_ID: &str = "render-mode";
+/// Label, action name and mode of every entry in the render mode menu.
+pub const RENDER_MODES: [(&str, &str, RenderMode); 4] = [
+    ("Solid", "solid", RenderMode::Solid),
+    ("Wireframe", "wireframe", RenderMode::Wireframe),
+    (
+        "Solid with edges",
+        "solid-edges",
+        RenderMode::SolidWithEdges,
+    ),
+    ("Feature edges", "feature-edges", RenderMode::FeatureEdges),
+];
+pub const WIREFRAME_COLOR: [f32; 4] = [0.85, 0.87, 0.9, 1.0];
+pub const EDGE_COLOR: [f32; 4] = [0.1, 0.1, 0.12, 1.0];
+pub const GRID_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+pub const BOUNDING_BOX_COLOR: [f32; 4] = [0.95, 0.8, 0.3, 1.0];
+/// Colours of the `x`, `y` and `z` axis of the gizmo.
+pub const AXIS_COLORS: [[f32; 4]; 3] = [
+    [0.9, 0.25, 0.2, 1.0],
+    [0.3, 0.8, 0.3, 1.0],
+    [0.3, 0.5, 1.0, 1.0],
+];
+/// Width and height of the axis gizmo in pixels.
+pub const GIZMO_SIZE: i32 = 96;
+pub const LABELS_ID: &str = "labels";
+pub const SCREENSHOT_FILE_NAME: &str = "screenshot.png";
+/// Milliseconds a downloaded image stays available to the browser.
+pub const OBJECT_URL_LIFETIME: i32 = 10_000;
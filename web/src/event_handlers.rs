@@ -0,0 +1,216 @@
+use crate::constants::{DROP_TARGET_CLASS, MIDDLE_BUTTON, RIGHT_BUTTON, TEXT_INPUT_TAGS};
+use crate::utils::{resize_canvas, window};
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::prelude::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    DragEvent, Element, Event, EventTarget, File, HtmlCanvasElement, KeyboardEvent, MouseEvent,
+    PointerEvent, WheelEvent,
+};
+use wedge::keymap::Keymap;
+use wedge::view::{DragMode, ViewerState};
+
+pub fn set_event_handlers(
+    canvas: HtmlCanvasElement,
+    state: Rc<RefCell<ViewerState>>,
+    keymap: Keymap,
+) {
+    let event_target: EventTarget = canvas.clone().into();
+    // RESIZE
+    {
+        let canvas = canvas.clone();
+        let resize_cb = Closure::wrap(Box::new(move |_event: Event| {
+            resize_canvas(canvas.clone());
+        }) as Box<dyn FnMut(Event)>);
+        window()
+            .add_event_listener_with_callback("resize", resize_cb.as_ref().unchecked_ref())
+            .unwrap();
+        resize_cb.forget();
+    }
+
+    // ZOOM
+    {
+        let state = state.clone();
+        let canvas = canvas.clone();
+        let zoom_cb = Closure::wrap(Box::new(move |event: WheelEvent| {
+            // The page itself does not scroll.
+            event.prevent_default();
+            state.borrow_mut().wheel(
+                event.delta_y() as f32,
+                [event.offset_x() as f32, event.offset_y() as f32],
+                [canvas.width() as f32, canvas.height() as f32],
+            );
+        }) as Box<dyn FnMut(WheelEvent)>);
+        event_target
+            .add_event_listener_with_callback("wheel", zoom_cb.as_ref().unchecked_ref())
+            .unwrap();
+        zoom_cb.forget();
+    }
+
+    // POINTERDOWN
+    {
+        let canvas = canvas.clone();
+        let state = state.clone();
+        let pointerdown_cb = Closure::wrap(Box::new(move |event: PointerEvent| {
+            // Keep the middle button from starting to autoscroll.
+            event.prevent_default();
+            // Keep receiving the moves when the pointer leaves the canvas.
+            canvas.set_pointer_capture(event.pointer_id()).unwrap();
+            let mode = match event.button() {
+                MIDDLE_BUTTON | RIGHT_BUTTON => DragMode::Pan,
+                _ if event.shift_key() => DragMode::Pan,
+                _ => DragMode::Rotate,
+            };
+            state.borrow_mut().pointer_down(
+                event.pointer_id(),
+                [event.offset_x() as f32, event.offset_y() as f32],
+                mode,
+            );
+        }) as Box<dyn FnMut(PointerEvent)>);
+        event_target
+            .add_event_listener_with_callback(
+                "pointerdown",
+                pointerdown_cb.as_ref().unchecked_ref(),
+            )
+            .unwrap();
+        pointerdown_cb.forget();
+    }
+
+    // CONTEXTMENU
+    {
+        let contextmenu_cb = Closure::wrap(Box::new(move |event: MouseEvent| {
+            // Right drag pans, so the menu would only get in the way.
+            event.prevent_default();
+        }) as Box<dyn FnMut(MouseEvent)>);
+        event_target
+            .add_event_listener_with_callback(
+                "contextmenu",
+                contextmenu_cb.as_ref().unchecked_ref(),
+            )
+            .unwrap();
+        contextmenu_cb.forget();
+    }
+
+    // POINTERUP and POINTERCANCEL
+    {
+        let state = state.clone();
+        let pointerup_cb = Closure::wrap(Box::new(move |event: PointerEvent| {
+            state.borrow_mut().pointer_up(event.pointer_id());
+        }) as Box<dyn FnMut(PointerEvent)>);
+        event_target
+            .add_event_listener_with_callback("pointerup", pointerup_cb.as_ref().unchecked_ref())
+            .unwrap();
+        event_target
+            .add_event_listener_with_callback(
+                "pointercancel",
+                pointerup_cb.as_ref().unchecked_ref(),
+            )
+            .unwrap();
+        pointerup_cb.forget();
+    }
+
+    // KEYDOWN
+    {
+        let state = state.clone();
+        let keydown_cb = Closure::wrap(Box::new(move |event: KeyboardEvent| {
+            // Leave the shortcuts of the browser alone.
+            if event.ctrl_key() || event.meta_key() || event.alt_key() {
+                return;
+            }
+            // Nor typing into the controls, such as the screenshot size.
+            let tag = event
+                .target()
+                .and_then(|target| target.dyn_into::<Element>().ok())
+                .map(|element| element.tag_name());
+            if tag.is_some_and(|tag| TEXT_INPUT_TAGS.contains(&tag.as_str())) {
+                return;
+            }
+            if let Some(action) = keymap.action(&event.key()) {
+                event.prevent_default();
+                state.borrow_mut().apply(action);
+            }
+        }) as Box<dyn FnMut(KeyboardEvent)>);
+        window()
+            .add_event_listener_with_callback("keydown", keydown_cb.as_ref().unchecked_ref())
+            .unwrap();
+        keydown_cb.forget();
+    }
+
+    // POINTERMOVE
+    {
+        let pointermove_cb = Closure::wrap(Box::new(move |event: PointerEvent| {
+            state.borrow_mut().pointer_move(
+                event.pointer_id(),
+                [event.offset_x() as f32, event.offset_y() as f32],
+                [canvas.width() as f32, canvas.height() as f32],
+            );
+        }) as Box<dyn FnMut(PointerEvent)>);
+        event_target
+            .add_event_listener_with_callback(
+                "pointermove",
+                pointermove_cb.as_ref().unchecked_ref(),
+            )
+            .unwrap();
+        pointermove_cb.forget();
+    }
+}
+
+pub fn set_drop_handlers<F>(canvas: HtmlCanvasElement, on_drop: F)
+where
+    F: Fn(Vec<File>) + 'static,
+{
+    let event_target: EventTarget = canvas.clone().into();
+
+    // DRAGENTER and DRAGOVER
+    {
+        let canvas = canvas.clone();
+        let dragover_cb = Closure::wrap(Box::new(move |event: DragEvent| {
+            // Dropping is only allowed if the default is prevented.
+            event.prevent_default();
+            if let Some(data_transfer) = event.data_transfer() {
+                data_transfer.set_drop_effect("copy");
+            }
+            canvas.set_class_name(DROP_TARGET_CLASS);
+        }) as Box<dyn FnMut(DragEvent)>);
+        event_target
+            .add_event_listener_with_callback("dragenter", dragover_cb.as_ref().unchecked_ref())
+            .unwrap();
+        event_target
+            .add_event_listener_with_callback("dragover", dragover_cb.as_ref().unchecked_ref())
+            .unwrap();
+        dragover_cb.forget();
+    }
+
+    // DRAGLEAVE
+    {
+        let canvas = canvas.clone();
+        let dragleave_cb = Closure::wrap(Box::new(move |_event: DragEvent| {
+            canvas.set_class_name("");
+        }) as Box<dyn FnMut(DragEvent)>);
+        event_target
+            .add_event_listener_with_callback("dragleave", dragleave_cb.as_ref().unchecked_ref())
+            .unwrap();
+        dragleave_cb.forget();
+    }
+
+    // DROP
+    {
+        let drop_cb = Closure::wrap(Box::new(move |event: DragEvent| {
+            event.prevent_default();
+            canvas.set_class_name("");
+            let files = match event.data_transfer().and_then(|d| d.files()) {
+                Some(files) => files,
+                None => return,
+            };
+            on_drop(
+                (0..files.length())
+                    .filter_map(|idx| files.get(idx))
+                    .collect(),
+            );
+        }) as Box<dyn FnMut(DragEvent)>);
+        event_target
+            .add_event_listener_with_callback("drop", drop_cb.as_ref().unchecked_ref())
+            .unwrap();
+        drop_cb.forget();
+    }
+}
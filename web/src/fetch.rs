@@ -0,0 +1,63 @@
+use crate::utils::{set_status, window};
+use js_sys::{Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{ReadableStreamDefaultReader, Request, RequestInit, RequestMode, Response};
+
+/// Downloads `url`, reporting the progress in the status line.
+pub async fn fetch_bytes(url: &str) -> Result<Vec<u8>, JsValue> {
+    let opts = RequestInit::new();
+    opts.set_method("GET");
+    opts.set_mode(RequestMode::Cors);
+    let request = Request::new_with_str_and_init(url, &opts)?;
+
+    set_status(&format!("Loading {}", url));
+    let response: Response = JsFuture::from(window().fetch_with_request(&request))
+        .await?
+        .dyn_into()?;
+    if !response.ok() {
+        return Err(JsValue::from_str(&format!(
+            "server responded with {} {}",
+            response.status(),
+            response.status_text()
+        )));
+    }
+
+    let total = response
+        .headers()
+        .get("content-length")?
+        .and_then(|length| length.parse::<usize>().ok());
+    let body = response
+        .body()
+        .ok_or_else(|| JsValue::from_str("response has no body"))?;
+    let reader = ReadableStreamDefaultReader::new(&body)?;
+
+    let mut bytes = vec![];
+    loop {
+        let chunk = JsFuture::from(reader.read()).await?;
+        if Reflect::get(&chunk, &"done".into())?.is_truthy() {
+            break;
+        }
+        let value = Reflect::get(&chunk, &"value".into())?;
+        bytes.extend(Uint8Array::new(&value).to_vec());
+
+        match total {
+            Some(total) if total > 0 => set_status(&format!(
+                "Loading {}: {}%",
+                url,
+                (bytes.len() * 100 / total).min(100)
+            )),
+            _ => set_status(&format!("Loading {}: {} kB", url, bytes.len() / 1024)),
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Extracts a readable message from an error thrown by a web API.
+pub fn error_message(error: &JsValue) -> String {
+    match error.dyn_ref::<js_sys::Error>() {
+        Some(error) => error.message().into(),
+        None => error.as_string().unwrap_or_else(|| format!("{:?}", error)),
+    }
+}
@@ -0,0 +1,58 @@
+use crate::constants::LABELS_ID;
+use crate::utils::window;
+use wasm_bindgen::prelude::*;
+use web_sys::HtmlElement;
+
+/// Text at a position in pixels from the top left corner of the canvas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScreenLabel {
+    pub text: String,
+    pub position: [f32; 2],
+    pub class: &'static str,
+}
+
+/// Text laid over the canvas. The elements are kept between frames and only
+/// hidden when there are fewer labels to show.
+pub struct Labels {
+    container: HtmlElement,
+    elements: Vec<HtmlElement>,
+}
+
+impl Labels {
+    pub fn new() -> Result<Self, JsValue> {
+        let container = window()
+            .document()
+            .and_then(|document| document.get_element_by_id(LABELS_ID))
+            .ok_or("should have a labels element")?
+            .dyn_into::<HtmlElement>()?;
+        Ok(Self {
+            container,
+            elements: vec![],
+        })
+    }
+
+    pub fn show(&mut self, labels: &[ScreenLabel]) -> Result<(), JsValue> {
+        let document = window()
+            .document()
+            .expect("should have a document on window");
+        while self.elements.len() < labels.len() {
+            let element = document.create_element("span")?.dyn_into::<HtmlElement>()?;
+            self.container.append_child(&element)?;
+            self.elements.push(element);
+        }
+
+        for (element, label) in self.elements.iter().zip(labels) {
+            let [x, y] = label.position;
+            element.set_text_content(Some(&label.text));
+            element.set_class_name(label.class);
+            let style = element.style();
+            style.set_property("left", &format!("{}px", x))?;
+            style.set_property("top", &format!("{}px", y))?;
+            style.remove_property("display")?;
+        }
+        for element in &self.elements[labels.len()..] {
+            element.style().set_property("display", "none")?;
+        }
+        Ok(())
+    }
+}
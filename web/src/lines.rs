@@ -0,0 +1,146 @@
+use crate::constants::COMPONENTS_PER_VERTEX;
+use crate::float_32_array;
+use crate::utils::init_shader_program;
+use js_sys::WebAssembly;
+use wasm_bindgen::prelude::*;
+use web_sys::{WebGlBuffer, WebGlProgram, WebGlRenderingContext, WebGlUniformLocation};
+use wedge::mesh::Mesh;
+
+/// Unlit program drawing lines in a single colour, for edges and overlays.
+#[derive(Debug, Clone)]
+pub struct LineProgramInfo(
+    WebGlProgram,
+    u32,
+    (
+        Result<WebGlUniformLocation, String>,
+        Result<WebGlUniformLocation, String>,
+        Result<WebGlUniformLocation, String>,
+    ),
+);
+
+/// Buffer of line segments, two `x, y, z` positions each, and the number of
+/// vertices in it.
+#[derive(Debug, Clone)]
+pub struct Lines(WebGlBuffer, i32);
+
+impl LineProgramInfo {
+    pub fn new(gl: &WebGlRenderingContext) -> Result<Self, JsValue> {
+        let vertex_shader_source = r#"
+            attribute vec4 aVertexPosition;
+            uniform mat4 uModelViewMatrix;
+            uniform mat4 uProjectionMatrix;
+
+            void main(void) {
+                gl_Position = uProjectionMatrix * uModelViewMatrix * aVertexPosition;
+            }
+        "#;
+
+        let fragment_shader_source = r#"
+            precision mediump float;
+
+            uniform vec4 uColor;
+
+            void main(void) {
+                gl_FragColor = uColor;
+            }
+        "#;
+
+        let shader_program = init_shader_program(gl, vertex_shader_source, fragment_shader_source)?;
+
+        let vertex_pos = gl.get_attrib_location(&shader_program, "aVertexPosition") as u32;
+        let projection_matrix = gl
+            .get_uniform_location(&shader_program, "uProjectionMatrix")
+            .ok_or_else(|| String::from("cannot get uProjectionMatrix"));
+        let model_view_matrix = gl
+            .get_uniform_location(&shader_program, "uModelViewMatrix")
+            .ok_or_else(|| String::from("cannot get uModelViewMatrix"));
+        let color = gl
+            .get_uniform_location(&shader_program, "uColor")
+            .ok_or_else(|| String::from("cannot get uColor"));
+        Ok(Self(
+            shader_program,
+            vertex_pos,
+            (projection_matrix, model_view_matrix, color),
+        ))
+    }
+
+    pub fn draw(
+        &self,
+        gl: &WebGlRenderingContext,
+        lines: &Lines,
+        color: [f32; 4],
+        projection_matrix: &[f32; 16],
+        model_view_matrix: &[f32; 16],
+    ) -> Result<(), JsValue> {
+        let LineProgramInfo(
+            shader_program,
+            vertex_position,
+            (location_projection_matrix, location_model_view_matrix, location_color),
+        ) = self;
+        let Lines(buffer, num_vertices) = lines;
+
+        gl.use_program(Some(shader_program));
+        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(buffer));
+        gl.vertex_attrib_pointer_with_i32(
+            *vertex_position,
+            COMPONENTS_PER_VERTEX,
+            WebGlRenderingContext::FLOAT,
+            false,
+            0,
+            0,
+        );
+        gl.enable_vertex_attrib_array(*vertex_position);
+        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, None);
+
+        gl.uniform_matrix4fv_with_f32_array(
+            Some(location_projection_matrix.as_ref()?),
+            false,
+            projection_matrix,
+        );
+        gl.uniform_matrix4fv_with_f32_array(
+            Some(location_model_view_matrix.as_ref()?),
+            false,
+            model_view_matrix,
+        );
+        gl.uniform4fv_with_f32_array(Some(location_color.as_ref()?), &color);
+
+        gl.draw_arrays(WebGlRenderingContext::LINES, 0, *num_vertices);
+        gl.disable_vertex_attrib_array(*vertex_position);
+        Ok(())
+    }
+}
+
+impl Lines {
+    pub fn new(gl: &WebGlRenderingContext, positions: &[f32]) -> Result<Self, JsValue> {
+        let buffer = gl.create_buffer().ok_or("failed to create lines buffer")?;
+        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&buffer));
+
+        let position_array = float_32_array!(positions);
+        gl.buffer_data_with_array_buffer_view(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            &position_array,
+            WebGlRenderingContext::STATIC_DRAW,
+        );
+        Ok(Self(
+            buffer,
+            (positions.len() / COMPONENTS_PER_VERTEX as usize) as i32,
+        ))
+    }
+
+    /// Segments between the mesh vertices given by pairs of indices.
+    pub fn from_edges(
+        gl: &WebGlRenderingContext,
+        mesh: &Mesh,
+        edges: &[u32],
+    ) -> Result<Self, JsValue> {
+        let positions: Vec<f32> = edges
+            .iter()
+            .flat_map(|&idx| <[f32; 3]>::from(mesh.position(idx)))
+            .collect();
+        Self::new(gl, &positions)
+    }
+
+    pub fn delete(&self, gl: &WebGlRenderingContext) {
+        gl.delete_buffer(Some(&self.0));
+    }
+}
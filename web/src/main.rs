@@ -0,0 +1,381 @@
+use constants::{
+    RENDER_MODES, RENDER_MODE_SELECT_ID, SCREENSHOT_FILE_NAME, STL_EXTENSION, TOGGLES,
+    WELD_TOLERANCE,
+};
+use js_sys::Uint8Array;
+use std::cell::RefCell;
+use std::error::Error;
+use std::rc::Rc;
+use utils::{set_status, window};
+use viewer::Viewer;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
+use web_sys::{
+    console, Event, File, FileReader, HtmlButtonElement, HtmlCanvasElement, HtmlDivElement,
+    HtmlInputElement, HtmlOptionElement, HtmlSelectElement, UrlSearchParams,
+};
+use wedge::keymap::{Action, Keymap};
+use wedge::mesh::Mesh;
+use wedge::stl;
+
+mod constants;
+mod event_handlers;
+mod fetch;
+mod labels;
+mod lines;
+mod screenshot;
+mod utils;
+mod viewer;
+
+fn main() {
+    set_panic_hook();
+    let mut problems = vec![];
+    let keymap = keymap().unwrap_or_else(|e| {
+        problems.push(e.to_string());
+        Keymap::default()
+    });
+    let viewer = Rc::new(RefCell::new(
+        Viewer::new(canvas().unwrap(), keymap).unwrap(),
+    ));
+    match transition_duration() {
+        Ok(Some(duration)) => viewer.borrow_mut().set_transition_duration(duration),
+        Ok(None) => {}
+        Err(e) => problems.push(e.to_string()),
+    }
+    report(&problems);
+    viewer::start(viewer.clone());
+
+    set_file_reader(viewer.clone()).unwrap();
+    set_toggles(viewer.clone()).unwrap();
+    set_render_mode_select(viewer.clone()).unwrap();
+    set_screenshot_controls(viewer.clone()).unwrap();
+    set_drop_target(viewer.clone()).unwrap();
+    load_from_query(viewer).unwrap()
+}
+
+fn canvas() -> Result<HtmlCanvasElement, JsValue> {
+    let document = window()
+        .document()
+        .expect("should have a document on window");
+    let canvas = document.get_element_by_id("canvas").unwrap();
+    canvas.dyn_into::<HtmlCanvasElement>().map_err(Into::into)
+}
+
+/// Default key bindings, overridden by the `keys` query parameter like
+/// `?keys=w=shading,s=render-mode`.
+fn keymap() -> Result<Keymap, Box<dyn Error>> {
+    let mut keymap = Keymap::default();
+    let params = query_params().map_err(|e| fetch::error_message(&e))?;
+    if let Some(bindings) = params.get("keys") {
+        keymap.configure(&bindings)?;
+    }
+    Ok(keymap)
+}
+
+/// Length of camera moves in seconds from the `transition` query parameter,
+/// given in milliseconds. `?transition=0` turns the animation off.
+fn transition_duration() -> Result<Option<f32>, Box<dyn Error>> {
+    let params = query_params().map_err(|e| fetch::error_message(&e))?;
+    let millis = match params.get("transition") {
+        Some(millis) => millis,
+        None => return Ok(None),
+    };
+    let millis: f32 = millis
+        .parse()
+        .map_err(|_| format!("Invalid transition duration: {}", millis))?;
+    Ok(Some(millis / 1000.0))
+}
+
+fn query_params() -> Result<UrlSearchParams, JsValue> {
+    UrlSearchParams::new_with_str(&window().location().search()?)
+}
+
+/// Loads the model linked by the `model` query parameter, if any.
+fn load_from_query(viewer: Rc<RefCell<Viewer>>) -> Result<(), JsValue> {
+    if let Some(url) = query_params()?.get("model") {
+        spawn_local(async move {
+            match fetch::fetch_bytes(&url).await {
+                Ok(bytes) => report(&load_models(&[(url, bytes)], &viewer)),
+                Err(e) => report(&[format!(
+                    "Failed to load {}: {}",
+                    url,
+                    fetch::error_message(&e)
+                )]),
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Loads every `.stl` file dropped onto the canvas as a single scene.
+fn set_drop_target(viewer: Rc<RefCell<Viewer>>) -> Result<(), JsValue> {
+    event_handlers::set_drop_handlers(canvas()?, move |files: Vec<File>| {
+        let viewer = viewer.clone();
+        spawn_local(async move {
+            let (files, unsupported): (Vec<File>, Vec<File>) = files
+                .into_iter()
+                .partition(|file| file.name().to_lowercase().ends_with(STL_EXTENSION));
+
+            let mut models = vec![];
+            let mut problems = vec![];
+            for file in files {
+                match JsFuture::from(file.array_buffer()).await {
+                    Ok(buffer) => models.push((file.name(), Uint8Array::new(&buffer).to_vec())),
+                    Err(e) => problems.push(format!(
+                        "Failed to read {}: {}",
+                        file.name(),
+                        fetch::error_message(&e)
+                    )),
+                }
+            }
+
+            problems.extend(load_models(&models, &viewer));
+            if !unsupported.is_empty() {
+                let names: Vec<String> = unsupported.iter().map(|file| file.name()).collect();
+                problems.push(format!("Unsupported file type: {}", names.join(", ")));
+            }
+            report(&problems);
+        });
+    });
+    Ok(())
+}
+
+/// Parses every model into a single scene, the parts keep their positions
+/// relative to each other. Returns a message for every file that failed.
+fn load_models(models: &[(String, Vec<u8>)], viewer: &RefCell<Viewer>) -> Vec<String> {
+    let mut scene: Option<Mesh> = None;
+    let mut problems = vec![];
+
+    for (name, bytes) in models {
+        match stl::get_data(bytes, WELD_TOLERANCE) {
+            Ok(mesh) => match scene.as_mut() {
+                Some(scene) => scene.append(&mesh),
+                None => scene = Some(mesh),
+            },
+            Err(e) => problems.push(format!("The given file is corrupted: {}: {}", name, e)),
+        }
+    }
+
+    if let Some(scene) = scene {
+        // Uploading a huge model to the GPU may fail.
+        if let Err(e) = viewer.borrow_mut().set_mesh(scene) {
+            problems.push(format!(
+                "Failed to display the model: {}",
+                fetch::error_message(&e)
+            ));
+        }
+    }
+    problems
+}
+
+/// Logs the problems and shows them in the status line, clearing it if there
+/// are none.
+fn report(problems: &[String]) {
+    for problem in problems {
+        console::log_1(&problem.into());
+    }
+    set_status(&problems.join("; "));
+}
+
+fn set_file_reader(viewer: Rc<RefCell<Viewer>>) -> Result<(), JsValue> {
+    let document = window()
+        .document()
+        .expect("should have a document on window");
+    let file_in_div = document.get_element_by_id("file-input-div").unwrap();
+    let file_in_div: HtmlDivElement = file_in_div.dyn_into()?;
+
+    let fileinput: HtmlInputElement = document
+        .create_element("input")?
+        .dyn_into::<HtmlInputElement>()?;
+
+    fileinput.set_id("file-input");
+    fileinput.set_class_name("file-input");
+    fileinput.set_type("file");
+
+    let filereader = FileReader::new()?;
+    let file_name = Rc::new(RefCell::new(String::new()));
+    let loaded_file_name = file_name.clone();
+
+    let closure = Closure::wrap(Box::new(move |event: Event| {
+        let element = event.target().unwrap().dyn_into::<FileReader>().unwrap();
+        let buffer = Uint8Array::new(&element.result().unwrap());
+        let v = buffer.to_vec();
+
+        let models = [(loaded_file_name.borrow().clone(), v)];
+        report(&load_models(&models, &viewer));
+    }) as Box<dyn FnMut(_)>);
+
+    filereader.set_onloadend(Some(closure.as_ref().unchecked_ref()));
+    closure.forget();
+
+    let closure = Closure::wrap(Box::new(move |event: Event| {
+        let element = event
+            .target()
+            .unwrap()
+            .dyn_into::<HtmlInputElement>()
+            .unwrap();
+        let filelist = element.files().unwrap();
+        let file = filelist.get(0).expect("should have a file handle.");
+        *file_name.borrow_mut() = file.name();
+        filereader.read_as_array_buffer(&file).unwrap();
+    }) as Box<dyn FnMut(_)>);
+
+    fileinput.add_event_listener_with_callback("change", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+
+    file_in_div.append_child(&fileinput)?;
+    Ok(())
+}
+
+/// A checkbox for every display option that can be toggled.
+fn set_toggles(viewer: Rc<RefCell<Viewer>>) -> Result<(), JsValue> {
+    let document = window()
+        .document()
+        .expect("should have a document on window");
+    let file_in_div = document.get_element_by_id("file-input-div").unwrap();
+    let toggles = document.create_element("div")?;
+    toggles.set_class_name("toggles");
+
+    for (id, text, name, enabled) in TOGGLES {
+        let label = document.create_element("label")?;
+        label.set_class_name("toggle");
+
+        let checkbox: HtmlInputElement = document
+            .create_element("input")?
+            .dyn_into::<HtmlInputElement>()?;
+        checkbox.set_id(id);
+        checkbox.set_type("checkbox");
+        checkbox.set_checked(enabled(&viewer.borrow().display()));
+
+        let action: Action = name.parse().unwrap();
+        let viewer = viewer.clone();
+        let closure = Closure::wrap(Box::new(move |event: Event| {
+            let element = event
+                .target()
+                .unwrap()
+                .dyn_into::<HtmlInputElement>()
+                .unwrap();
+            let mut viewer = viewer.borrow_mut();
+            if element.checked() != enabled(&viewer.display()) {
+                viewer.apply(action);
+            }
+        }) as Box<dyn FnMut(_)>);
+
+        checkbox.add_event_listener_with_callback("change", closure.as_ref().unchecked_ref())?;
+        closure.forget();
+
+        label.append_child(&checkbox)?;
+        label.append_with_str_2(" ", text)?;
+        toggles.append_child(&label)?;
+    }
+    file_in_div.append_child(&toggles)?;
+    Ok(())
+}
+
+fn set_render_mode_select(viewer: Rc<RefCell<Viewer>>) -> Result<(), JsValue> {
+    let document = window()
+        .document()
+        .expect("should have a document on window");
+    let file_in_div = document.get_element_by_id("file-input-div").unwrap();
+
+    let label = document.create_element("label")?;
+    label.set_class_name("render-mode");
+    label.append_with_str_1("Render mode ")?;
+
+    let select: HtmlSelectElement = document
+        .create_element("select")?
+        .dyn_into::<HtmlSelectElement>()?;
+    select.set_id(RENDER_MODE_SELECT_ID);
+    for (text, name, _) in RENDER_MODES {
+        let option = HtmlOptionElement::new_with_text_and_value(text, name)?;
+        select.append_child(&option)?;
+    }
+
+    let closure = Closure::wrap(Box::new(move |event: Event| {
+        let element = event
+            .target()
+            .unwrap()
+            .dyn_into::<HtmlSelectElement>()
+            .unwrap();
+        if let Ok(action) = element.value().parse() {
+            viewer.borrow_mut().apply(action);
+        }
+    }) as Box<dyn FnMut(_)>);
+
+    select.add_event_listener_with_callback("change", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+
+    label.append_child(&select)?;
+    file_in_div.append_child(&label)?;
+    Ok(())
+}
+
+/// Size of the image, which defaults to the size of the canvas, a
+/// transparent background option and a button saving the image.
+fn set_screenshot_controls(viewer: Rc<RefCell<Viewer>>) -> Result<(), JsValue> {
+    let document = window()
+        .document()
+        .expect("should have a document on window");
+    let file_in_div = document.get_element_by_id("file-input-div").unwrap();
+    let controls = document.create_element("div")?;
+    controls.set_class_name("screenshot");
+
+    let canvas = canvas()?;
+    let mut sizes = vec![];
+    for (text, size) in [("Width ", canvas.width()), (" Height ", canvas.height())] {
+        let input: HtmlInputElement = document
+            .create_element("input")?
+            .dyn_into::<HtmlInputElement>()?;
+        input.set_type("number");
+        input.set_min("1");
+        input.set_value(&size.to_string());
+        controls.append_with_str_1(text)?;
+        controls.append_child(&input)?;
+        sizes.push(input);
+    }
+
+    let label = document.create_element("label")?;
+    let transparent: HtmlInputElement = document
+        .create_element("input")?
+        .dyn_into::<HtmlInputElement>()?;
+    transparent.set_type("checkbox");
+    label.append_child(&transparent)?;
+    label.append_with_str_1(" Transparent")?;
+    controls.append_child(&label)?;
+
+    let button: HtmlButtonElement = document
+        .create_element("button")?
+        .dyn_into::<HtmlButtonElement>()?;
+    button.set_text_content(Some("Save image"));
+
+    let closure = Closure::wrap(Box::new(move |_event: Event| {
+        let [width, height] = [&sizes[0], &sizes[1]].map(|input| input.value_as_number());
+        // Empty or fractional sizes are rounded, the viewer rejects the rest.
+        let [width, height] = [width, height].map(|size| size.round().max(0.0) as u32);
+        let result = viewer
+            .borrow()
+            .screenshot(width, height, transparent.checked())
+            .and_then(|pixels| {
+                screenshot::download_png(&pixels, width, height, SCREENSHOT_FILE_NAME)
+            });
+        match result {
+            Ok(()) => report(&[]),
+            Err(e) => report(&[format!(
+                "Failed to save image: {}",
+                fetch::error_message(&e)
+            )]),
+        }
+    }) as Box<dyn FnMut(_)>);
+
+    button.add_event_listener_with_callback("click", closure.as_ref().unchecked_ref())?;
+    closure.forget();
+
+    controls.append_child(&button)?;
+    file_in_div.append_child(&controls)?;
+    Ok(())
+}
+
+fn set_panic_hook() {
+    #[cfg(feature = "console_error_panic_hook")]
+    console_error_panic_hook::set_once();
+}
@@ -0,0 +1,60 @@
+use crate::constants::OBJECT_URL_LIFETIME;
+use crate::utils::window;
+use wasm_bindgen::{prelude::*, Clamped};
+use web_sys::{
+    Blob, CanvasRenderingContext2d, HtmlAnchorElement, HtmlCanvasElement, ImageData, Url,
+};
+
+/// Encodes RGBA pixels, top row first, as PNG and downloads them as
+/// `file_name`. The browser does the encoding through a 2D canvas.
+pub fn download_png(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    file_name: &str,
+) -> Result<(), JsValue> {
+    let document = window()
+        .document()
+        .expect("should have a document on window");
+    let canvas = document
+        .create_element("canvas")?
+        .dyn_into::<HtmlCanvasElement>()?;
+    canvas.set_width(width);
+    canvas.set_height(height);
+    let context = canvas
+        .get_context("2d")?
+        .ok_or("failed to get 2d context")?
+        .dyn_into::<CanvasRenderingContext2d>()?;
+    let image = ImageData::new_with_u8_clamped_array_and_sh(Clamped(pixels), width, height)?;
+    context.put_image_data(&image, 0.0, 0.0)?;
+
+    let file_name = file_name.to_string();
+    let callback = Closure::once_into_js(move |blob: Option<Blob>| {
+        if let Some(blob) = blob {
+            download(&blob, &file_name).unwrap();
+        }
+    });
+    canvas.to_blob(callback.unchecked_ref())
+}
+
+fn download(blob: &Blob, file_name: &str) -> Result<(), JsValue> {
+    let document = window()
+        .document()
+        .expect("should have a document on window");
+    let url = Url::create_object_url_with_blob(blob)?;
+    let anchor = document
+        .create_element("a")?
+        .dyn_into::<HtmlAnchorElement>()?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+
+    // The download starts asynchronously, revoking the URL right away may
+    // cancel it.
+    let revoke = Closure::once_into_js(move || Url::revoke_object_url(&url));
+    window().set_timeout_with_callback_and_timeout_and_arguments_0(
+        revoke.unchecked_ref(),
+        OBJECT_URL_LIFETIME,
+    )?;
+    Ok(())
+}
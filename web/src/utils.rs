@@ -5,6 +5,16 @@ pub fn window() -> web_sys::Window {
     web_sys::window().expect("no global `window` exists")
 }
 
+/// Shows `msg` in the status line, an empty message hides it.
+pub fn set_status(msg: &str) {
+    let status = window()
+        .document()
+        .expect("should have a document on window")
+        .get_element_by_id("status")
+        .expect("should have a status element");
+    status.set_text_content(Some(msg));
+}
+
 pub fn resize_canvas(canvas: HtmlCanvasElement) {
     let new_width = window().inner_width().unwrap().as_f64().unwrap();
     let new_height = window().inner_height().unwrap().as_f64().unwrap();
@@ -88,7 +98,18 @@ pub fn link_program(
     }
 }
 
-pub fn request_animation_frame(f: &Closure<dyn FnMut(f32)>) {
+pub fn init_shader_program(
+    gl: &WebGlRenderingContext,
+    vs_source: &str,
+    fs_source: &str,
+) -> Result<WebGlProgram, String> {
+    let v_shader = compile_shader(gl, WebGlRenderingContext::VERTEX_SHADER, vs_source);
+    let f_shader = compile_shader(gl, WebGlRenderingContext::FRAGMENT_SHADER, fs_source);
+
+    link_program(gl, &v_shader?, &f_shader?)
+}
+
+pub fn request_animation_frame(f: &Closure<dyn FnMut(f64)>) {
     window()
         .request_animation_frame(f.as_ref().unchecked_ref())
         .expect("should register `requestAnimationFrame` OK");
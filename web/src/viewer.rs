@@ -0,0 +1,839 @@
+use crate::constants::{
+    AXIS_COLORS, BOUNDING_BOX_COLOR, COMPONENTS_PER_VERTEX, EDGE_COLOR, GIZMO_SIZE, GRID_COLOR,
+    RENDER_MODES, RENDER_MODE_SELECT_ID, TOGGLES, WIREFRAME_COLOR,
+};
+use crate::event_handlers;
+use crate::labels::{Labels, ScreenLabel};
+use crate::lines::{LineProgramInfo, Lines};
+use crate::utils::{init_shader_program, request_animation_frame, resize_canvas, window};
+use crate::{float_32_array, uint_32_array};
+use js_sys::WebAssembly;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use web_sys::{
+    HtmlCanvasElement, HtmlInputElement, HtmlSelectElement, WebGlBuffer, WebGlProgram,
+    WebGlRenderingContext, WebGlUniformLocation,
+};
+use wedge::keymap::{Action, Keymap};
+use wedge::linalg::BoundingBox;
+use wedge::mesh::{Mesh, ShadedMesh, DEFAULT_COLOR};
+use wedge::overlay::{self, Grid, Label};
+use wedge::render::{
+    AMBIENT, BACKGROUND_COLOR, CREASE_ANGLE, DIFFUSE, LIGHT_DIRECTION, SHININESS, SPECULAR,
+};
+use wedge::view::{DisplayOptions, RenderMode, ViewerState};
+
+#[derive(Debug, Clone)]
+struct ProgramInfo(
+    WebGlProgram,
+    (u32, u32, u32),
+    (
+        Result<WebGlUniformLocation, String>,
+        Result<WebGlUniformLocation, String>,
+    ),
+);
+
+/// Position, normal, index and, for a coloured mesh, colour buffer.
+#[derive(Debug, Clone)]
+struct Buffers(WebGlBuffer, WebGlBuffer, WebGlBuffer, Option<WebGlBuffer>);
+
+/// Mesh currently on screen together with its GPU buffers.
+struct Model {
+    mesh: Mesh,
+    buffers: Buffers,
+    num_indices: u32,
+    edges: Lines,
+    feature_edges: Lines,
+    /// Shading the buffers were built with.
+    smooth_shading: bool,
+    /// Angle the feature edges were found with.
+    feature_angle: f32,
+    /// Helpers drawn around the model, missing for an empty one.
+    helpers: Option<Helpers>,
+}
+
+/// Grid and bounding box of a model.
+struct Helpers {
+    grid: Grid,
+    grid_lines: Lines,
+    box_lines: Lines,
+    dimensions: Vec<Label>,
+}
+
+/// Owns the WebGL context for the lifetime of the page. Loading another mesh
+/// replaces the current one in place, so there is only ever one render loop
+/// and one set of event handlers.
+pub struct Viewer {
+    gl: WebGlRenderingContext,
+    canvas: HtmlCanvasElement,
+    program_info: ProgramInfo,
+    line_program_info: LineProgramInfo,
+    /// One line per axis of the gizmo.
+    axes: Vec<Lines>,
+    labels: Labels,
+    model: Option<Model>,
+    state: Rc<RefCell<ViewerState>>,
+    /// Display options last shown by the controls.
+    display: DisplayOptions,
+}
+
+impl Viewer {
+    pub fn new(canvas: HtmlCanvasElement, keymap: Keymap) -> Result<Self, JsValue> {
+        let gl = canvas
+            .get_context("webgl")?
+            .unwrap()
+            .dyn_into::<WebGlRenderingContext>()?;
+
+        let [r, g, b, a] = BACKGROUND_COLOR;
+        gl.clear_color(r, g, b, a);
+        gl.clear_depth(1.0);
+        gl.enable(WebGlRenderingContext::DEPTH_TEST);
+        gl.get_extension("OES_element_index_uint").unwrap();
+        // Push surfaces back so lines on them, like edges or the grid under
+        // the model, pass the depth test.
+        gl.enable(WebGlRenderingContext::POLYGON_OFFSET_FILL);
+        gl.polygon_offset(1.0, 1.0);
+
+        let vertex_shader_source = r#"
+            attribute vec4 aVertexPosition;
+            attribute vec3 aVertexNormal;
+            attribute vec3 aVertexColor;
+            uniform mat4 uModelViewMatrix;
+            uniform mat4 uProjectionMatrix;
+
+            varying mediump vec3 vPosition;
+            varying mediump vec3 vNormal;
+            varying mediump vec3 vColor;
+
+            void main(void) {
+                vec4 position = uModelViewMatrix * aVertexPosition;
+                gl_Position = uProjectionMatrix * position;
+                vPosition = position.xyz;
+                // The model view matrix only rotates, translates and scales uniformly.
+                vNormal = (uModelViewMatrix * vec4(aVertexNormal, 0.0)).xyz;
+                vColor = aVertexColor;
+            }
+        "#;
+
+        // Blinn-Phong with a directional light attached to the camera, shared with
+        // the software renderer.
+        let fragment_shader_source = format!(
+            r#"
+            precision mediump float;
+
+            varying mediump vec3 vPosition;
+            varying mediump vec3 vNormal;
+            varying mediump vec3 vColor;
+
+            const vec3 lightDirection = vec3({:?}, {:?}, {:?});
+            const float ambient = {:?};
+            const float diffuse = {:?};
+            const float specular = {:?};
+            const float shininess = {:?};
+
+            void main(void) {{
+                vec3 normal = normalize(vNormal);
+                // STL files are not required to wind their facets consistently.
+                if (!gl_FrontFacing) {{
+                    normal = -normal;
+                }}
+                vec3 view = normalize(-vPosition);
+                vec3 halfway = normalize(lightDirection + view);
+
+                float lambert = max(dot(normal, lightDirection), 0.0);
+                float highlight = lambert > 0.0 ? pow(max(dot(normal, halfway), 0.0), shininess) : 0.0;
+
+                vec3 color = vColor * (ambient + diffuse * lambert) + vec3(specular * highlight);
+                gl_FragColor = vec4(color, 1.0);
+            }}
+        "#,
+            LIGHT_DIRECTION[0],
+            LIGHT_DIRECTION[1],
+            LIGHT_DIRECTION[2],
+            AMBIENT,
+            DIFFUSE,
+            SPECULAR,
+            SHININESS,
+        );
+
+        let shader_program =
+            init_shader_program(&gl, vertex_shader_source, &fragment_shader_source)?;
+
+        let program_info = {
+            let vertex_pos = gl.get_attrib_location(&shader_program, "aVertexPosition") as u32;
+            let vertex_normal = gl.get_attrib_location(&shader_program, "aVertexNormal") as u32;
+            let vertex_color = gl.get_attrib_location(&shader_program, "aVertexColor") as u32;
+            let projection_matrix = gl
+                .get_uniform_location(&shader_program, "uProjectionMatrix")
+                .ok_or_else(|| String::from("cannot get uProjectionMatrix"));
+            let model_view_matrix = gl
+                .get_uniform_location(&shader_program, "uModelViewMatrix")
+                .ok_or_else(|| String::from("cannot get uModelViewMatrix"));
+            ProgramInfo(
+                shader_program,
+                (vertex_pos, vertex_normal, vertex_color),
+                (projection_matrix, model_view_matrix),
+            )
+        };
+        let line_program_info = LineProgramInfo::new(&gl)?;
+        let axes = overlay::axis_lines()
+            .chunks(2 * COMPONENTS_PER_VERTEX as usize)
+            .map(|axis| Lines::new(&gl, axis))
+            .collect::<Result<_, _>>()?;
+
+        let state = Rc::new(RefCell::new(ViewerState::default()));
+        event_handlers::set_event_handlers(canvas.clone(), state.clone(), keymap);
+
+        resize_canvas(canvas.clone());
+
+        Ok(Self {
+            gl,
+            canvas,
+            program_info,
+            line_program_info,
+            axes,
+            labels: Labels::new()?,
+            model: None,
+            display: DisplayOptions::default(),
+            state,
+        })
+    }
+
+    /// Replaces the current mesh, releasing its buffers, and frames it.
+    pub fn set_mesh(&mut self, mesh: Mesh) -> Result<(), JsValue> {
+        if let Some(model) = self.model.take() {
+            delete_model(&self.gl, &model);
+        }
+
+        let scene = mesh
+            .bounds()
+            .map(|bounds| bounds.bounding_sphere())
+            .unwrap_or_default();
+        let display = self.state.borrow().display;
+        self.model = Some(upload(&self.gl, mesh, &display)?);
+
+        self.state.borrow_mut().set_scene(scene);
+        Ok(())
+    }
+
+    pub fn display(&self) -> DisplayOptions {
+        self.state.borrow().display
+    }
+
+    pub fn apply(&mut self, action: Action) {
+        self.state.borrow_mut().apply(action);
+    }
+
+    /// Length of animated camera moves in seconds.
+    pub fn set_transition_duration(&mut self, duration: f32) {
+        self.state.borrow_mut().transition_duration = duration;
+    }
+
+    /// Advances the camera by a frame that took `elapsed` seconds and draws
+    /// the scene. The buffers are rebuilt first if the shading or the
+    /// feature angle changed since the last frame.
+    fn tick(&mut self, elapsed: f32) -> Result<(), JsValue> {
+        self.state.borrow_mut().tick(elapsed);
+
+        let display = self.state.borrow().display;
+        if let Some(model) = self.model.take() {
+            if model.smooth_shading == display.smooth_shading
+                && model.feature_angle == display.feature_angle
+            {
+                self.model = Some(model);
+            } else {
+                delete_model(&self.gl, &model);
+                self.model = Some(upload(&self.gl, model.mesh, &display)?);
+            }
+        }
+        if display != self.display {
+            sync_controls(&display);
+            self.display = display;
+        }
+
+        let labels = self.render([self.canvas.width() as i32, self.canvas.height() as i32])?;
+        self.labels.show(&labels)
+    }
+
+    /// Renders the current view at `width` by `height` pixels into an
+    /// offscreen framebuffer and returns its RGBA pixels, top row first. The
+    /// labels are laid over the canvas by the page, so they are left out.
+    pub fn screenshot(
+        &self,
+        width: u32,
+        height: u32,
+        transparent: bool,
+    ) -> Result<Vec<u8>, JsValue> {
+        let gl = &self.gl;
+        let max_size = [
+            WebGlRenderingContext::MAX_RENDERBUFFER_SIZE,
+            WebGlRenderingContext::MAX_TEXTURE_SIZE,
+        ]
+        .into_iter()
+        .map(|limit| {
+            gl.get_parameter(limit)
+                .map(|size| size.as_f64().unwrap_or(0.0) as u32)
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .min()
+        .unwrap_or(0);
+        if width == 0 || height == 0 || width.max(height) > max_size {
+            return Err(format!("Image size must be between 1 and {} pixels", max_size).into());
+        }
+        let (width, height) = (width as i32, height as i32);
+
+        let texture = gl
+            .create_texture()
+            .ok_or("failed to create screenshot texture")?;
+        gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, Some(&texture));
+        gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+            WebGlRenderingContext::TEXTURE_2D,
+            0,
+            WebGlRenderingContext::RGBA as i32,
+            width,
+            height,
+            0,
+            WebGlRenderingContext::RGBA,
+            WebGlRenderingContext::UNSIGNED_BYTE,
+            None,
+        )?;
+        // Textures of any size are complete without mipmaps and repetition.
+        for (parameter, value) in [
+            (
+                WebGlRenderingContext::TEXTURE_MIN_FILTER,
+                WebGlRenderingContext::LINEAR,
+            ),
+            (
+                WebGlRenderingContext::TEXTURE_WRAP_S,
+                WebGlRenderingContext::CLAMP_TO_EDGE,
+            ),
+            (
+                WebGlRenderingContext::TEXTURE_WRAP_T,
+                WebGlRenderingContext::CLAMP_TO_EDGE,
+            ),
+        ] {
+            gl.tex_parameteri(WebGlRenderingContext::TEXTURE_2D, parameter, value as i32);
+        }
+        gl.bind_texture(WebGlRenderingContext::TEXTURE_2D, None);
+
+        let depth_buffer = gl
+            .create_renderbuffer()
+            .ok_or("failed to create screenshot depth buffer")?;
+        gl.bind_renderbuffer(WebGlRenderingContext::RENDERBUFFER, Some(&depth_buffer));
+        gl.renderbuffer_storage(
+            WebGlRenderingContext::RENDERBUFFER,
+            WebGlRenderingContext::DEPTH_COMPONENT16,
+            width,
+            height,
+        );
+        gl.bind_renderbuffer(WebGlRenderingContext::RENDERBUFFER, None);
+
+        let framebuffer = gl
+            .create_framebuffer()
+            .ok_or("failed to create screenshot framebuffer")?;
+        gl.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, Some(&framebuffer));
+        gl.framebuffer_texture_2d(
+            WebGlRenderingContext::FRAMEBUFFER,
+            WebGlRenderingContext::COLOR_ATTACHMENT0,
+            WebGlRenderingContext::TEXTURE_2D,
+            Some(&texture),
+            0,
+        );
+        gl.framebuffer_renderbuffer(
+            WebGlRenderingContext::FRAMEBUFFER,
+            WebGlRenderingContext::DEPTH_ATTACHMENT,
+            WebGlRenderingContext::RENDERBUFFER,
+            Some(&depth_buffer),
+        );
+
+        let pixels = if gl.check_framebuffer_status(WebGlRenderingContext::FRAMEBUFFER)
+            == WebGlRenderingContext::FRAMEBUFFER_COMPLETE
+        {
+            if transparent {
+                gl.clear_color(0.0, 0.0, 0.0, 0.0);
+            }
+            let pixels = self.read_frame(width, height);
+            let [r, g, b, a] = BACKGROUND_COLOR;
+            gl.clear_color(r, g, b, a);
+            pixels
+        } else {
+            Err("Image size is not supported".into())
+        };
+
+        gl.bind_framebuffer(WebGlRenderingContext::FRAMEBUFFER, None);
+        gl.delete_framebuffer(Some(&framebuffer));
+        gl.delete_renderbuffer(Some(&depth_buffer));
+        gl.delete_texture(Some(&texture));
+        pixels
+    }
+
+    /// Renders into the bound framebuffer and reads it back, flipped so the
+    /// top row comes first.
+    fn read_frame(&self, width: i32, height: i32) -> Result<Vec<u8>, JsValue> {
+        self.render([width, height])?;
+        let row = width as usize * 4;
+        let mut pixels = vec![0; row * height as usize];
+        self.gl.read_pixels_with_opt_u8_array(
+            0,
+            0,
+            width,
+            height,
+            WebGlRenderingContext::RGBA,
+            WebGlRenderingContext::UNSIGNED_BYTE,
+            Some(&mut pixels),
+        )?;
+        Ok(pixels.chunks_exact(row).rev().flatten().copied().collect())
+    }
+
+    /// Draws the current view into a viewport of the given size and returns
+    /// the labels that go with it.
+    fn render(&self, viewport: [i32; 2]) -> Result<Vec<ScreenLabel>, JsValue> {
+        let [width, height] = viewport;
+        self.gl.viewport(0, 0, width, height);
+        self.gl.clear(
+            WebGlRenderingContext::COLOR_BUFFER_BIT | WebGlRenderingContext::DEPTH_BUFFER_BIT,
+        );
+        let model = match &self.model {
+            Some(model) => model,
+            None => return Ok(vec![]),
+        };
+
+        let state = self.state.borrow();
+        draw_scene(
+            &self.gl,
+            &self.program_info,
+            &self.line_program_info,
+            model,
+            &state,
+            viewport,
+        )?;
+        let mut labels = draw_helpers(&self.gl, &self.line_program_info, model, &state, viewport)?;
+        if state.display.axes {
+            labels.extend(self.draw_gizmo(&state, viewport)?);
+        }
+        Ok(labels)
+    }
+
+    /// Draws the axes turned like the model in the bottom left corner and
+    /// returns their labels. The gizmo keeps its size relative to the canvas
+    /// in larger images.
+    fn draw_gizmo(
+        &self,
+        state: &ViewerState,
+        [_, height]: [i32; 2],
+    ) -> Result<Vec<ScreenLabel>, JsValue> {
+        let scale = height as f32 / self.canvas.height().max(1) as f32;
+        let size = (GIZMO_SIZE as f32 * scale).round();
+        let (projection_matrix, view_matrix) = overlay::gizmo_matrices(state.camera.rotation);
+        // The gizmo stays on top of the model.
+        self.gl.disable(WebGlRenderingContext::DEPTH_TEST);
+        self.gl.viewport(0, 0, size as i32, size as i32);
+        for (axis, color) in self.axes.iter().zip(AXIS_COLORS) {
+            self.line_program_info
+                .draw(&self.gl, axis, color, &projection_matrix, &view_matrix)?;
+        }
+        self.gl.enable(WebGlRenderingContext::DEPTH_TEST);
+
+        let height = height as f32;
+        let labels = overlay::axis_labels()
+            .into_iter()
+            .zip(["axis-x", "axis-y", "axis-z"])
+            .filter_map(|(label, class)| {
+                let [x, y] = overlay::project(&projection_matrix, &view_matrix, label.position)?;
+                Some(ScreenLabel {
+                    text: label.text,
+                    position: [(x + 1.0) * 0.5 * size, height - (y + 1.0) * 0.5 * size],
+                    class,
+                })
+            })
+            .collect();
+        Ok(labels)
+    }
+}
+
+/// Starts the render loop, which keeps running for the lifetime of the page.
+pub fn start(viewer: Rc<RefCell<Viewer>>) {
+    let f = Rc::new(RefCell::new(None));
+    let g = f.clone();
+
+    let mut last_time = None;
+    *g.borrow_mut() = Some(Closure::wrap(Box::new(move |time: f64| {
+        // The time is in milliseconds.
+        let elapsed = last_time.map_or(0.0, |last_time| (time - last_time) / 1000.0);
+        last_time = Some(time);
+        viewer.borrow_mut().tick(elapsed as f32).unwrap();
+        request_animation_frame(f.borrow().as_ref().unwrap());
+    }) as Box<dyn FnMut(f64)>));
+
+    request_animation_frame(g.borrow().as_ref().unwrap());
+}
+
+fn shade_mesh(mesh: &Mesh, smooth: bool) -> ShadedMesh {
+    if smooth {
+        mesh.smooth_shaded(CREASE_ANGLE)
+    } else {
+        mesh.flat_shaded()
+    }
+}
+
+fn upload(
+    gl: &WebGlRenderingContext,
+    mesh: Mesh,
+    display: &DisplayOptions,
+) -> Result<Model, JsValue> {
+    let shaded = shade_mesh(&mesh, display.smooth_shading);
+    Ok(Model {
+        buffers: init_buffers(gl, &shaded)?,
+        num_indices: shaded.indices.len() as u32,
+        edges: Lines::from_edges(gl, &mesh, &mesh.edges())?,
+        feature_edges: Lines::from_edges(gl, &mesh, &mesh.feature_edges(display.feature_angle))?,
+        smooth_shading: display.smooth_shading,
+        feature_angle: display.feature_angle,
+        helpers: mesh
+            .bounds()
+            .map(|bounds| upload_helpers(gl, &bounds))
+            .transpose()?,
+        mesh,
+    })
+}
+
+fn upload_helpers(gl: &WebGlRenderingContext, bounds: &BoundingBox) -> Result<Helpers, JsValue> {
+    let grid = Grid::new(bounds);
+    Ok(Helpers {
+        grid,
+        grid_lines: Lines::new(gl, &grid.lines())?,
+        box_lines: Lines::new(gl, &overlay::box_lines(bounds))?,
+        dimensions: overlay::dimension_labels(bounds),
+    })
+}
+
+fn delete_model(gl: &WebGlRenderingContext, model: &Model) {
+    let Buffers(position_buffer, normal_buffer, index_buffer, color_buffer) = &model.buffers;
+    gl.delete_buffer(Some(position_buffer));
+    gl.delete_buffer(Some(normal_buffer));
+    gl.delete_buffer(Some(index_buffer));
+    gl.delete_buffer(color_buffer.as_ref());
+    model.edges.delete(gl);
+    model.feature_edges.delete(gl);
+    if let Some(helpers) = &model.helpers {
+        helpers.grid_lines.delete(gl);
+        helpers.box_lines.delete(gl);
+    }
+}
+
+/// Keeps the controls in line when the display options are changed from the
+/// keyboard.
+fn sync_controls(display: &DisplayOptions) {
+    let document = match window().document() {
+        Some(document) => document,
+        None => return,
+    };
+    for (id, _, _, enabled) in TOGGLES {
+        let checkbox = document
+            .get_element_by_id(id)
+            .and_then(|element| element.dyn_into::<HtmlInputElement>().ok());
+        if let Some(checkbox) = checkbox {
+            checkbox.set_checked(enabled(display));
+        }
+    }
+    let select = document
+        .get_element_by_id(RENDER_MODE_SELECT_ID)
+        .and_then(|element| element.dyn_into::<HtmlSelectElement>().ok());
+    let name = RENDER_MODES
+        .iter()
+        .find(|(_, _, mode)| *mode == display.render_mode)
+        .map(|(_, name, _)| *name);
+    if let (Some(select), Some(name)) = (select, name) {
+        select.set_value(name);
+    }
+}
+
+fn init_buffers(gl: &WebGlRenderingContext, mesh: &ShadedMesh) -> Result<Buffers, JsValue> {
+    let position_buffer = gl
+        .create_buffer()
+        .ok_or("failed to create positionBuffer buffer")?;
+
+    gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&position_buffer));
+
+    let position_array = float_32_array!(mesh.positions);
+    gl.buffer_data_with_array_buffer_view(
+        WebGlRenderingContext::ARRAY_BUFFER,
+        &position_array,
+        WebGlRenderingContext::STATIC_DRAW,
+    );
+
+    let normal_buffer = gl
+        .create_buffer()
+        .ok_or("failed to create normalBuffer buffer")?;
+
+    gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&normal_buffer));
+
+    let normal_array = float_32_array!(mesh.normals);
+    gl.buffer_data_with_array_buffer_view(
+        WebGlRenderingContext::ARRAY_BUFFER,
+        &normal_array,
+        WebGlRenderingContext::STATIC_DRAW,
+    );
+
+    let index_buffer = gl
+        .create_buffer()
+        .ok_or("failed to create indexBuffer buffer")?;
+    gl.bind_buffer(
+        WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+        Some(&index_buffer),
+    );
+
+    let index_array = uint_32_array!(mesh.indices);
+    gl.buffer_data_with_array_buffer_view(
+        WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+        &index_array,
+        WebGlRenderingContext::STATIC_DRAW,
+    );
+
+    let color_buffer = if mesh.colors.is_empty() {
+        None
+    } else {
+        let color_buffer = gl
+            .create_buffer()
+            .ok_or("failed to create colorBuffer buffer")?;
+
+        gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(&color_buffer));
+
+        let color_array = float_32_array!(mesh.colors);
+        gl.buffer_data_with_array_buffer_view(
+            WebGlRenderingContext::ARRAY_BUFFER,
+            &color_array,
+            WebGlRenderingContext::STATIC_DRAW,
+        );
+        Some(color_buffer)
+    };
+    Ok(Buffers(
+        position_buffer,
+        normal_buffer,
+        index_buffer,
+        color_buffer,
+    ))
+}
+
+fn draw_scene(
+    gl: &WebGlRenderingContext,
+    program_info: &ProgramInfo,
+    line_program_info: &LineProgramInfo,
+    model: &Model,
+    state: &ViewerState,
+    viewport: [i32; 2],
+) -> Result<(), JsValue> {
+    let projection_matrix = projection_matrix(model, state, viewport);
+    let model_view_matrix = state.camera.view_matrix();
+
+    match state.display.render_mode {
+        RenderMode::Solid => {
+            draw_solid(
+                gl,
+                program_info,
+                model,
+                &projection_matrix,
+                &model_view_matrix,
+            )?;
+        }
+        RenderMode::Wireframe => {
+            line_program_info.draw(
+                gl,
+                &model.edges,
+                WIREFRAME_COLOR,
+                &projection_matrix,
+                &model_view_matrix,
+            )?;
+        }
+        RenderMode::SolidWithEdges => {
+            draw_solid(
+                gl,
+                program_info,
+                model,
+                &projection_matrix,
+                &model_view_matrix,
+            )?;
+            line_program_info.draw(
+                gl,
+                &model.edges,
+                EDGE_COLOR,
+                &projection_matrix,
+                &model_view_matrix,
+            )?;
+        }
+        RenderMode::FeatureEdges => {
+            line_program_info.draw(
+                gl,
+                &model.feature_edges,
+                WIREFRAME_COLOR,
+                &projection_matrix,
+                &model_view_matrix,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Draws the grid and the bounding box, if shown, and returns the labels
+/// that go with them.
+fn draw_helpers(
+    gl: &WebGlRenderingContext,
+    line_program_info: &LineProgramInfo,
+    model: &Model,
+    state: &ViewerState,
+    viewport: [i32; 2],
+) -> Result<Vec<ScreenLabel>, JsValue> {
+    let helpers = match &model.helpers {
+        Some(helpers) => helpers,
+        None => return Ok(vec![]),
+    };
+    let projection_matrix = projection_matrix(model, state, viewport);
+    let model_view_matrix = state.camera.view_matrix();
+
+    let mut labels = vec![];
+    if state.display.grid {
+        line_program_info.draw(
+            gl,
+            &helpers.grid_lines,
+            GRID_COLOR,
+            &projection_matrix,
+            &model_view_matrix,
+        )?;
+        labels.push((helpers.grid.label(), "grid"));
+    }
+    if state.display.bounding_box {
+        line_program_info.draw(
+            gl,
+            &helpers.box_lines,
+            BOUNDING_BOX_COLOR,
+            &projection_matrix,
+            &model_view_matrix,
+        )?;
+        labels.extend(
+            helpers
+                .dimensions
+                .iter()
+                .map(|label| (label.clone(), "dimension")),
+        );
+    }
+
+    let [width, height] = viewport.map(|size| size as f32);
+    let labels = labels
+        .into_iter()
+        .filter_map(|(label, class)| {
+            let [x, y] = overlay::project(&projection_matrix, &model_view_matrix, label.position)?;
+            Some(ScreenLabel {
+                text: label.text,
+                position: [(x + 1.0) * 0.5 * width, (1.0 - y) * 0.5 * height],
+                class,
+            })
+        })
+        .collect();
+    Ok(labels)
+}
+
+/// Projection for the viewport, with clip planes that keep the grid in view
+/// when it is shown.
+fn projection_matrix(model: &Model, state: &ViewerState, [width, height]: [i32; 2]) -> [f32; 16] {
+    let aspect_ratio: f32 = width as f32 / height as f32;
+    let scene = match &model.helpers {
+        Some(helpers) if state.display.grid => state.scene.union(&helpers.grid.bounding_sphere()),
+        _ => state.scene,
+    };
+    state.camera.projection_matrix(aspect_ratio, &scene)
+}
+
+fn draw_solid(
+    gl: &WebGlRenderingContext,
+    program_info: &ProgramInfo,
+    model: &Model,
+    projection_matrix: &[f32; 16],
+    model_view_matrix: &[f32; 16],
+) -> Result<(), JsValue> {
+    let Buffers(position_buffer, normal_buffer, index_buffer, color_buffer) = &model.buffers;
+    let ProgramInfo(
+        shader_program,
+        (vertex_position, vertex_normal, vertex_color),
+        (location_projection_matrix, location_model_view_matrix),
+    ) = program_info;
+
+    gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(position_buffer));
+    gl.vertex_attrib_pointer_with_i32(
+        *vertex_position,
+        COMPONENTS_PER_VERTEX,
+        WebGlRenderingContext::FLOAT,
+        false,
+        0,
+        0,
+    );
+
+    gl.enable_vertex_attrib_array(*vertex_position);
+
+    gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(normal_buffer));
+    gl.vertex_attrib_pointer_with_i32(
+        *vertex_normal,
+        COMPONENTS_PER_VERTEX,
+        WebGlRenderingContext::FLOAT,
+        false,
+        0,
+        0,
+    );
+
+    gl.enable_vertex_attrib_array(*vertex_normal);
+
+    match color_buffer {
+        Some(color_buffer) => {
+            gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, Some(color_buffer));
+            gl.vertex_attrib_pointer_with_i32(
+                *vertex_color,
+                COMPONENTS_PER_VERTEX,
+                WebGlRenderingContext::FLOAT,
+                false,
+                0,
+                0,
+            );
+            gl.enable_vertex_attrib_array(*vertex_color);
+        }
+        // Without an array every vertex gets the same colour.
+        None => {
+            let [r, g, b] = DEFAULT_COLOR;
+            gl.vertex_attrib3f(*vertex_color, r, g, b);
+        }
+    }
+
+    gl.bind_buffer(WebGlRenderingContext::ARRAY_BUFFER, None);
+
+    gl.use_program(Some(shader_program));
+
+    gl.uniform_matrix4fv_with_f32_array(
+        Some(location_projection_matrix.as_ref()?),
+        false,
+        projection_matrix,
+    );
+
+    gl.uniform_matrix4fv_with_f32_array(
+        Some(location_model_view_matrix.as_ref()?),
+        false,
+        model_view_matrix,
+    );
+
+    gl.bind_buffer(
+        WebGlRenderingContext::ELEMENT_ARRAY_BUFFER,
+        Some(index_buffer),
+    );
+    gl.draw_elements_with_i32(
+        WebGlRenderingContext::TRIANGLES,
+        model.num_indices as i32,
+        WebGlRenderingContext::UNSIGNED_INT,
+        0,
+    );
+
+    // The line program only reads positions.
+    gl.disable_vertex_attrib_array(*vertex_color);
+    gl.disable_vertex_attrib_array(*vertex_normal);
+    gl.disable_vertex_attrib_array(*vertex_position);
+    Ok(())
+}